@@ -0,0 +1,122 @@
+//! Generation throughput of `Grid::step` on large random boards, against a straightforward per-cell
+//! implementation like the one the bit-packed grid replaced, and on sparse boards where only a few tiles are
+//! active.
+//!
+//! Run with `cargo bench --bench step`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use game_of_life_bevy::grid::Grid;
+use game_of_life_bevy::patterns::{Orientation, Pattern};
+use game_of_life_bevy::rules::Rules;
+use game_of_life_bevy::topology::Topology;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+
+const SIZES: &[usize] = &[256, 1024, 2048];
+
+/// One `bool` per cell in nested rows, with each neighbor looked up through the topology
+struct PerCellGrid {
+    width: usize,
+    height: usize,
+    cells: Vec<Vec<bool>>,
+}
+
+impl PerCellGrid {
+    fn from_grid(grid: &Grid) -> Self {
+        Self {
+            width: grid.width(),
+            height: grid.height(),
+            cells: (0..grid.height())
+                .map(|y| (0..grid.width()).map(|x| grid.is_alive(x, y)).collect())
+                .collect(),
+        }
+    }
+
+    fn step(&mut self, rules: &Rules) {
+        let mut next = vec![vec![false; self.width]; self.height];
+        next.par_iter_mut().enumerate().for_each(|(y, row)| {
+            row.iter_mut().enumerate().for_each(|(x, cell)| {
+                let mut alive_neighbors = 0;
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        if dx == 0 && dy == 0 {
+                            continue;
+                        }
+                        let neighbor = Topology::Torus.wrap(x as isize + dx, y as isize + dy, self.width, self.height);
+                        if neighbor.is_some_and(|(nx, ny)| self.cells[ny][nx]) {
+                            alive_neighbors += 1;
+                        }
+                    }
+                }
+                *cell = if self.cells[y][x] {
+                    rules.survival_counts.contains(&alive_neighbors)
+                } else {
+                    rules.birth_counts.contains(&alive_neighbors)
+                };
+            });
+        });
+        self.cells = next;
+    }
+}
+
+fn step(c: &mut Criterion) {
+    let rules: Rules = "B3/S23".parse().unwrap();
+    let mut group = c.benchmark_group("step");
+    group.sample_size(20);
+
+    for &size in SIZES {
+        let grid = Grid::random(size, size, 0.2, &mut StdRng::seed_from_u64(42)); // 20% of cells alive
+        group.throughput(Throughput::Elements((size * size) as u64));
+
+        // Both implementations must agree before their speed is worth comparing
+        let mut bit_packed = grid.clone();
+        let mut per_cell = PerCellGrid::from_grid(&grid);
+        for _ in 0..8 {
+            bit_packed.step(&rules);
+            per_cell.step(&rules);
+        }
+        assert!(PerCellGrid::from_grid(&bit_packed).cells == per_cell.cells);
+
+        group.bench_with_input(BenchmarkId::new("bit-packed", size), &grid, |b, grid| {
+            let mut grid = grid.clone();
+            b.iter(|| grid.step(&rules));
+        });
+        group.bench_with_input(BenchmarkId::new("per-cell", size), &grid, |b, grid| {
+            let mut grid = PerCellGrid::from_grid(grid);
+            b.iter(|| grid.step(&rules));
+        });
+    }
+    group.finish();
+}
+
+/// Boards of increasing size holding the same few oscillators among a lattice of still lifes, so the work per
+/// generation should stay roughly the same as the area grows
+fn sparse_step(c: &mut Criterion) {
+    let rules: Rules = "B3/S23".parse().unwrap();
+    let mut group = c.benchmark_group("sparse-step");
+    group.sample_size(20);
+
+    for &size in SIZES {
+        let mut grid = Grid::new(size, size);
+        for y in (0..size).step_by(32) {
+            for x in (0..size).step_by(32) {
+                Pattern::Block.add_to_grid(&mut grid, &[], Orientation::default(), x as isize, y as isize);
+            }
+        }
+        for i in 0..4 {
+            // Between the blocks, far enough from them not to interact
+            Pattern::Pulsar.add_to_grid(&mut grid, &[], Orientation::default(), 64 * i + 8, 64 * i + 8);
+        }
+        grid.step(&rules); // The first step after placing cells looks at every tile
+
+        group.bench_with_input(BenchmarkId::new("bit-packed", size), &grid, |b, grid| {
+            let mut grid = grid.clone();
+            b.iter(|| grid.step(&rules));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, step, sparse_step);
+criterion_main!(benches);
@@ -1,20 +1,84 @@
-use crate::config::Mode;
-use crate::rules::RuleSet;
+use crate::config::{self, Mode};
 use clap::{Parser, ValueEnum};
+use game_of_life_bevy::rules::SelectedRules;
+use game_of_life_bevy::topology::Topology;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 pub struct Args {
-    /// Rule set to use
-    #[arg(long, value_enum, default_value = "conway")]
-    pub rules: RuleSet,
+    /// Rule set to use: a preset name, a Life-like rulestring such as B36/S23, 23/3, B2/S013V or B2/S34H, an
+    /// isotropic non-totalistic rulestring such as B2-a/S12 or B3/S23-q, a Generations rulestring such as B2/S/C3
+    /// or 345/2/4, a Larger than Life rulestring such as R5,C0,M1,S34..58,B34..45,NM, or a Margolus block rule
+    /// such as MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15, or WireWorld
+    #[arg(long, default_value = "conway")]
+    pub rules: SelectedRules,
 
     /// Simulation speed in ticks per second
     #[arg(long, default_value = "30.0")]
     pub speed: f64,
 
+    /// Start with the simulation paused
+    #[arg(long)]
+    pub paused: bool,
+
     /// Display mode: color or image
     #[arg(long, value_enum, default_value = "color")]
     pub mode: DisplayMode,
+
+    /// Number of cells across the grid
+    #[arg(long, default_value_t = config::DEFAULT_GRID_WIDTH, value_parser = grid_size)]
+    pub width: usize,
+
+    /// Number of cells down the grid
+    #[arg(long, default_value_t = config::DEFAULT_GRID_HEIGHT, value_parser = grid_size)]
+    pub height: usize,
+
+    /// How the edges of the grid are joined; sphere needs equal width and height
+    #[arg(long, value_enum, default_value = "torus")]
+    pub topology: Topology,
+
+    /// Simulation engine: a fixed-size grid, an unbounded HashLife universe, or an unbounded sparse universe
+    /// stepped one generation at a time; the unbounded engines need two-state square 3x3 rules without B0
+    #[arg(long, value_enum, default_value = "grid")]
+    pub engine: Engine,
+
+    /// With the hashlife engine, each tick advances 2^N generations
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "0",
+        value_parser = clap::value_parser!(u32).range(0..=config::MAX_STEP_EXPONENT as i64)
+    )]
+    pub step_exponent: u32,
+
+    /// Width and height of each cell in pixels
+    #[arg(long, default_value_t = config::DEFAULT_CELL_SIZE)]
+    pub cell_size: f32,
+
+    /// RLE pattern file to add to the pattern list; can be repeated
+    #[arg(long = "pattern", value_name = "FILE")]
+    pub patterns: Vec<PathBuf>,
+
+    /// Switch to the rule declared in a loaded pattern's header when placing it
+    #[arg(long)]
+    pub pattern_rules: bool,
+
+    /// File the live region of the grid is saved to when pressing E (default: export.rle), or at the end of a
+    /// headless run; use a .cells extension for plaintext
+    #[arg(long, value_name = "FILE")]
+    pub export: Option<PathBuf>,
+
+    /// Run the simulation without a window and print the final population and timing
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Number of generations to run in headless mode, up to 2^58
+    #[arg(long, default_value = "1000", value_parser = clap::value_parser!(u64).range(..=config::MAX_GENERATIONS))]
+    pub generations: u64,
+
+    /// Random seed for the starting grid of a headless run without patterns
+    #[arg(long)]
+    pub seed: Option<u64>,
 }
 
 #[derive(ValueEnum, Clone)]
@@ -23,6 +87,14 @@ pub enum DisplayMode {
     Image,
 }
 
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    Grid,
+    #[value(name = "hashlife")]
+    HashLife,
+    Infinite,
+}
+
 impl From<DisplayMode> for Mode {
     fn from(display_mode: DisplayMode) -> Self {
         match display_mode {
@@ -31,3 +103,11 @@ impl From<DisplayMode> for Mode {
         }
     }
 }
+
+fn grid_size(s: &str) -> Result<usize, String> {
+    match s.parse() {
+        Ok(size) if size >= config::MIN_GRID_SIZE => Ok(size),
+        Ok(_) => Err(format!("must be at least {}", config::MIN_GRID_SIZE)),
+        Err(err) => Err(format!("{}", err)),
+    }
+}
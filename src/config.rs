@@ -1,10 +1,31 @@
 use bevy::prelude::Color;
 
-pub const GRID_WIDTH: usize = 250;
-pub const GRID_HEIGHT: usize = 250;
-pub const CELL_SIZE: f32 = 15.0;
+pub const DEFAULT_GRID_WIDTH: usize = 250;
+pub const DEFAULT_GRID_HEIGHT: usize = 250;
+pub const DEFAULT_CELL_SIZE: f32 = 15.0;
+pub const GRID_RESIZE_STEP: usize = 50; // Cells added or removed per side when resizing with page up/down
+pub const MIN_GRID_SIZE: usize = 10;
+pub const MIN_ZOOM: f32 = 0.05; // Camera scale when zoomed all the way in
+pub const MAX_ZOOM: f32 = 20.0; // Camera scale when zoomed all the way out
+pub const ZOOM_STEP: f32 = 1.1; // Scale factor per mouse wheel line
+pub const PIXELS_PER_SCROLL_LINE: f32 = 50.0; // Touchpads scroll in pixels rather than lines
+pub const CAMERA_PAN_SPEED: f32 = 600.0; // Screen pixels per second when panning with the keyboard
+pub const CAMERA_FOLLOW_RATE: f32 = 3.0; // How quickly the camera catches up with the live cells, per second
+pub const CAMERA_FOLLOW_MARGIN: f32 = 1.25; // Room left around the live cells when following them
+pub const HISTORY_MEMORY: usize = 256 << 20; // Bytes of grid states kept for undo/rewind
+pub const MAX_STEP_EXPONENT: u32 = 32; // Largest HashLife jump is 2^32 generations per tick
+pub const MAX_GENERATIONS: u64 = 1 << 58; // Longest headless run, so growth still fits HashLife's i64 coordinates
+pub const MIN_TICKS_PER_SECOND: f64 = 0.25;
+pub const MAX_TICKS_PER_SECOND: f64 = 960.0;
+pub const FAST_FORWARD_FRAME_BUDGET: f32 = 1.0 / 60.0; // Seconds per frame spent stepping in fast-forward mode
+pub const TICK_COUNTER_INTERVAL: f32 = 0.5; // Seconds between achieved ticks per second measurements
 pub const ALIVE_COLOR: Color = Color::srgb(0.0, 1.0, 0.0); // Green for alive cells
 pub const DEAD_COLOR: Color = Color::srgb(0.0, 0.0, 0.0); // Black for dead cells
+pub const DYING_COLOR: Color = Color::srgb(1.0, 0.5, 0.0); // Orange for newly dying cells, fading to dead
+pub const ELECTRON_HEAD_COLOR: Color = Color::srgb(0.0, 0.5, 1.0); // Blue for Wireworld electron heads
+pub const ELECTRON_TAIL_COLOR: Color = Color::srgb(1.0, 0.2, 0.0); // Red for Wireworld electron tails
+pub const CONDUCTOR_COLOR: Color = Color::srgb(1.0, 0.8, 0.0); // Yellow for Wireworld conductor
+pub const GHOST_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.4); // Translucent white for the placement preview
 
 pub const ALIVE_IMAGE: &str = "embedded://ferris.png";
 pub const DEAD_IMAGE: &str = "embedded://empty.png";
@@ -0,0 +1,129 @@
+use crate::grid::Grid;
+use crate::rules::Rules;
+use std::path::Path;
+
+/// Longest line written in RLE pattern data, as recommended by the format
+const RLE_LINE_LENGTH: usize = 70;
+
+/// Writes the live region of the grid to `path`: plaintext for `.cells` files, RLE otherwise
+pub fn write_file(path: &Path, grid: &Grid, rules: &Rules) -> std::io::Result<()> {
+    let contents = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("cells") => to_plaintext(grid, &path.file_stem().unwrap_or_default().to_string_lossy()),
+        _ => to_rle(grid, rules),
+    };
+    std::fs::write(path, contents)
+}
+
+/// Encodes the live region as RLE, with the rules in the header. Under Generations rules dying cells are kept
+/// too, written with the multi-state letters `.` (dead), `A` (alive), `B` and on (dying).
+pub fn to_rle(grid: &Grid, rules: &Rules) -> String {
+    let rows = live_rows(grid);
+    let width = rows.first().map_or(0, Vec::len);
+    let multi_state = rules.states > 2;
+
+    // Runs of `b` (dead) and `o` (alive), with `$` between rows and `!` at the end
+    let mut runs = Vec::new();
+    let mut line_ends = 0;
+    for (i, row) in rows.iter().enumerate() {
+        if i > 0 {
+            line_ends += 1;
+        }
+        let row_runs = row_runs(row, multi_state);
+        if row_runs.is_empty() {
+            continue; // Blank rows are folded into the next `$` run
+        }
+        if line_ends > 0 {
+            runs.push(run(line_ends, "$"));
+            line_ends = 0;
+        }
+        runs.extend(row_runs);
+    }
+    runs.push("!".to_string());
+
+    let mut rle = format!("x = {}, y = {}, rule = {}\n", width, rows.len(), rules);
+    let mut line_length = 0;
+    for run in runs {
+        if line_length + run.len() > RLE_LINE_LENGTH {
+            rle.push('\n');
+            line_length = 0;
+        }
+        line_length += run.len();
+        rle.push_str(&run);
+    }
+    rle.push('\n');
+    rle
+}
+
+/// Encodes the live region in the plaintext `.cells` format, `O` for alive and `.` for dead. The format has no
+/// dying states, so dying cells are written as dead.
+pub fn to_plaintext(grid: &Grid, name: &str) -> String {
+    let mut plaintext = format!("!Name: {}\n", name);
+    for row in live_rows(grid) {
+        plaintext.extend(row.iter().map(|&state| if state == 1 { 'O' } else { '.' }));
+        plaintext.push('\n');
+    }
+    plaintext
+}
+
+/// Cell states in the bounding box around all live and dying cells, top row first as both formats expect. A
+/// generation stored inverted for a B0 rule is written as it is drawn, with the background dead.
+fn live_rows(grid: &Grid) -> Vec<Vec<u8>> {
+    let Some((min_x, min_y, max_x, max_y)) = live_bounds(grid) else {
+        return Vec::new();
+    };
+    (min_y..=max_y)
+        .rev()
+        .map(|y| (min_x..=max_x).map(|x| grid.state(x, y)).collect())
+        .collect()
+}
+
+/// Smallest `(min_x, min_y, max_x, max_y)` box containing every live or dying cell
+fn live_bounds(grid: &Grid) -> Option<(usize, usize, usize, usize)> {
+    let mut bounds: Option<(usize, usize, usize, usize)> = None;
+    for y in 0..grid.height() {
+        for x in 0..grid.width() {
+            if grid.state(x, y) != 0 {
+                let (min_x, min_y, max_x, max_y) = bounds.unwrap_or((x, y, x, y));
+                bounds = Some((min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)));
+            }
+        }
+    }
+    bounds
+}
+
+/// Runs for a single row, leaving out trailing dead cells
+fn row_runs(row: &[u8], multi_state: bool) -> Vec<String> {
+    let live_length = row.iter().rposition(|&state| state != 0).map_or(0, |x| x + 1);
+    let mut runs = Vec::new();
+    let mut x = 0;
+    while x < live_length {
+        let state = row[x];
+        let count = row[x..live_length].iter().take_while(|&&other| other == state).count();
+        runs.push(run(count, &state_tag(state, multi_state)));
+        x += count;
+    }
+    runs
+}
+
+/// RLE letters for a cell state: `b`/`o` for two-state rules, otherwise `.` for dead, `A`..`X` for states 1-24,
+/// and a `p`..`y` prefix for the states above
+fn state_tag(state: u8, multi_state: bool) -> String {
+    match (state, multi_state) {
+        (0, false) => "b".to_string(),
+        (_, false) => "o".to_string(),
+        (0, true) => ".".to_string(),
+        (1..=24, true) => char::from(b'A' + state - 1).to_string(),
+        (_, true) => {
+            let (prefix, letter) = ((state - 25) / 24, (state - 25) % 24);
+            format!("{}{}", char::from(b'p' + prefix), char::from(b'A' + letter))
+        }
+    }
+}
+
+fn run(count: usize, tag: &str) -> String {
+    if count == 1 {
+        tag.to_string()
+    } else {
+        format!("{}{}", count, tag)
+    }
+}
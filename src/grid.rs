@@ -0,0 +1,1157 @@
+use crate::patterns::Board;
+use crate::rules::{Neighborhood, Rules};
+use crate::topology::Topology;
+use rand::Rng;
+use rayon::prelude::*;
+
+/// Rows in a tile; each tile is one word (64 cells) wide
+const TILE_ROWS: usize = 16;
+
+/// A fixed-size board of cells whose edges are joined according to its topology.
+///
+/// Cells are packed one bit per cell into contiguous rows of `u64` words. Each row carries a one-cell halo on
+/// every side, holding copies of the cells across the edge (or dead cells on a bounded grid), so a generation
+/// can be computed 64 cells at a time without looking up neighbors one by one.
+///
+/// The board is also divided into tiles of 64 x `TILE_ROWS` cells. Only tiles that changed in the last
+/// generation, or that border one that did, are recomputed; the rest are known to stay as they are.
+///
+/// Under Generations rules, cells that stop surviving spend a few generations dying before they are dead. Their
+/// ages are kept bit-sliced alongside the live cells, so dying cells advance 64 at a time too.
+///
+/// Rules with B0 would bring the whole background to life, so generations where it is alive are stored
+/// inverted instead, as `Rules::b0_emulation` describes. Everything outside the grid works with the stored cells,
+/// as they are drawn: editing, counting and exporting them.
+#[derive(Clone)]
+pub struct Grid {
+    width: usize,
+    height: usize,
+    topology: Topology,
+    /// Words per padded row, covering `width + 2` bits
+    stride: usize,
+    /// `height + 2` padded rows; cell `(x, y)` is bit `x + 1` of row `y + 1`
+    cells: Vec<u64>,
+    /// The buffer the next generation is written into before the two are swapped
+    next_cells: Vec<u64>,
+    /// Bit planes of each dying cell's age (state minus one), interleaved so word `w` of plane `k` is at
+    /// `w * planes + k`; zero for live and dead cells. Empty for two-state rules.
+    dying: Vec<u64>,
+    /// The buffer the next generation's ages are written into
+    next_dying: Vec<u64>,
+    /// Number of age bit planes, enough to count up to the last dying state
+    planes: usize,
+    /// Tiles that changed in the last generation or were edited since, in rows of `stride` tiles
+    changed_tiles: Vec<bool>,
+    /// Where the next generation records which tiles changed, swapped with `changed_tiles` after each step
+    next_changed_tiles: Vec<bool>,
+    /// The halo as last copied in, in `fill_halo` order, to tell which edges changed
+    halo: Vec<bool>,
+    /// Rules of the last step; a rule change can wake any tile
+    last_rules: Option<Rules>,
+    /// Whether the cells are stored inverted to emulate B0 rules, in which case cells off a bounded grid, which
+    /// are always dead, read as alive
+    inverted: bool,
+    /// Whether the next Margolus step groups cells into 2x2 blocks starting at odd columns and rows rather than
+    /// even ones; it alternates every step
+    odd_blocks: bool,
+    /// Scratch space for Margolus steps: the next cells of the blocks in each word, `stride` words for each pair of
+    /// rows. Empty until the first Margolus step.
+    next_blocks: Vec<[u64; 4]>,
+}
+
+impl Grid {
+    /// Creates a grid where every cell is dead
+    pub fn new(width: usize, height: usize) -> Self {
+        let stride = (width + 2).div_ceil(64);
+        let words = stride * (height + 2);
+        let tiles = stride * height.div_ceil(TILE_ROWS);
+        Self {
+            width,
+            height,
+            topology: Topology::default(),
+            stride,
+            cells: vec![0; words],
+            next_cells: vec![0; words],
+            dying: Vec::new(),
+            next_dying: Vec::new(),
+            planes: 0,
+            changed_tiles: vec![true; tiles],
+            next_changed_tiles: vec![false; tiles],
+            halo: vec![false; 2 * (width + height) + 4],
+            last_rules: None,
+            inverted: false,
+            odd_blocks: false,
+            next_blocks: Vec::new(),
+        }
+    }
+
+    /// Creates a grid where each cell is alive with the given probability
+    pub fn random(width: usize, height: usize, density: f64, rng: &mut impl Rng) -> Self {
+        Self::from_fn(width, height, |_, _| rng.gen_bool(density))
+    }
+
+    fn from_fn(width: usize, height: usize, mut is_alive: impl FnMut(usize, usize) -> bool) -> Self {
+        let mut grid = Self::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                if is_alive(x, y) {
+                    grid.set_alive(x, y, true);
+                }
+            }
+        }
+        grid
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Whether the cells are stored inverted to emulate a B0 rule
+    pub fn is_inverted(&self) -> bool {
+        self.inverted
+    }
+
+    pub fn set_inverted(&mut self, inverted: bool) {
+        self.inverted = inverted;
+        self.changed_tiles.fill(true);
+    }
+
+    /// Whether the next Margolus step starts its blocks at odd columns and rows
+    pub fn odd_blocks(&self) -> bool {
+        self.odd_blocks
+    }
+
+    pub fn set_odd_blocks(&mut self, odd_blocks: bool) {
+        self.odd_blocks = odd_blocks;
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+        self.changed_tiles.fill(true);
+    }
+
+    pub fn is_alive(&self, x: usize, y: usize) -> bool {
+        let (word, bit) = self.bit_index(x, y);
+        self.cells[word] & bit != 0
+    }
+
+    pub fn set_alive(&mut self, x: usize, y: usize, is_alive: bool) {
+        self.set_state(x, y, is_alive as u8);
+    }
+
+    /// State of cell `(x, y)`: 0 when dead, 1 when alive, and 2 or more while dying under Generations rules
+    pub fn state(&self, x: usize, y: usize) -> u8 {
+        let (word, bit) = self.bit_index(x, y);
+        if self.cells[word] & bit != 0 {
+            return 1;
+        }
+        let age = (0..self.planes).fold(0, |age, k| {
+            age | ((self.dying[word * self.planes + k] & bit != 0) as u8) << k
+        });
+        if age == 0 {
+            0
+        } else {
+            age + 1
+        }
+    }
+
+    /// Sets the state of cell `(x, y)`, adding age bit planes if a dying state needs more than there are
+    pub fn set_state(&mut self, x: usize, y: usize, state: u8) {
+        let age = state.saturating_sub(1);
+        if state > 1 {
+            self.set_planes(self.planes.max(planes_for_age(age as usize)));
+        }
+        let (word, bit) = self.bit_index(x, y);
+        if state == 1 {
+            self.cells[word] |= bit;
+        } else {
+            self.cells[word] &= !bit;
+        }
+        for k in 0..self.planes {
+            let plane = &mut self.dying[word * self.planes + k];
+            if age & 1 << k != 0 {
+                *plane |= bit;
+            } else {
+                *plane &= !bit;
+            }
+        }
+        self.mark_changed(x, y);
+    }
+
+    /// Changes the number of age bit planes, keeping the low bits of every age
+    fn set_planes(&mut self, planes: usize) {
+        if planes == self.planes {
+            return;
+        }
+        let mut dying = vec![0; self.cells.len() * planes];
+        for k in 0..self.planes.min(planes) {
+            for word in 0..self.cells.len() {
+                dying[word * planes + k] = self.dying[word * self.planes + k];
+            }
+        }
+        self.next_dying = vec![0; dying.len()];
+        self.dying = dying;
+        self.planes = planes;
+        self.changed_tiles.fill(true);
+    }
+
+    /// The packed cells and the interleaved age bit planes, halos included, along with the number of planes
+    pub(crate) fn words(&self) -> (&[u64], &[u64], usize) {
+        (&self.cells, &self.dying, self.planes)
+    }
+
+    /// Replaces the cells and ages with words taken from `words` on a grid of the same size
+    pub(crate) fn set_words(&mut self, cells: &[u64], dying: &[u64], planes: usize) {
+        debug_assert_eq!(cells.len(), self.cells.len());
+        self.cells.copy_from_slice(cells);
+        self.dying.clear();
+        self.dying.extend_from_slice(dying);
+        self.next_dying.resize(dying.len(), 0);
+        self.planes = planes;
+        self.changed_tiles.fill(true);
+    }
+
+    /// Flags the tile holding cell `(x, y)` so it and its neighbors are recomputed in the next step
+    fn mark_changed(&mut self, x: usize, y: usize) {
+        self.changed_tiles[y / TILE_ROWS * self.stride + (x + 1) / 64] = true;
+    }
+
+    /// Word index and bit mask of cell `(x, y)` within the padded rows
+    fn bit_index(&self, x: usize, y: usize) -> (usize, u64) {
+        debug_assert!(x < self.width && y < self.height);
+        let column = x + 1;
+        ((y + 1) * self.stride + column / 64, 1 << (column % 64))
+    }
+
+    /// Maps a position that may lie off the grid back onto it through the topology
+    pub fn wrap(&self, x: isize, y: isize) -> Option<(usize, usize)> {
+        self.topology.wrap(x, y, self.width, self.height)
+    }
+
+    /// Changes the grid size, keeping the existing cells centered
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let offset_x = (width as isize - self.width as isize) / 2;
+        let offset_y = (height as isize - self.height as isize) / 2;
+        let old = std::mem::replace(self, Grid::new(width, height));
+        self.topology = old.topology;
+        self.inverted = old.inverted;
+        self.odd_blocks = old.odd_blocks;
+        self.set_planes(old.planes);
+        for y in 0..old.height {
+            for x in 0..old.width {
+                let new_x = x as isize + offset_x;
+                let new_y = y as isize + offset_y;
+                let state = old.state(x, y);
+                if (0..width as isize).contains(&new_x) && (0..height as isize).contains(&new_y) && state != 0 {
+                    self.set_state(new_x as usize, new_y as usize, state);
+                }
+            }
+        }
+    }
+
+    /// Number of live cells as stored and drawn
+    pub fn population(&self) -> usize {
+        self.cells[self.stride..self.stride * (self.height + 1)]
+            .chunks_exact(self.stride)
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .map(|(i, word)| (word & cell_mask(self.width, i)).count_ones() as usize)
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+
+    /// Makes every cell dead
+    pub fn clear(&mut self) {
+        self.cells.fill(0);
+        self.dying.fill(0);
+        self.inverted = false;
+        self.changed_tiles.fill(true);
+    }
+
+    /// Complements the live cells, turning a generation stored inverted back into the real cells
+    fn uninvert(&mut self) {
+        let (stride, width) = (self.stride, self.width);
+        for row in self.cells[stride..stride * (self.height + 1)].chunks_exact_mut(stride) {
+            for (i, word) in row.iter_mut().enumerate() {
+                *word ^= cell_mask(width, i);
+            }
+        }
+        self.inverted = false;
+        self.changed_tiles.fill(true);
+    }
+
+    /// Advances the grid by one generation
+    pub fn step(&mut self, rules: &Rules) {
+        // Rules with more than two states have no opposite of their other states to store inverted, so the real cells
+        // come back
+        if self.inverted && rules.states > 2 {
+            self.uninvert();
+        }
+        // B0 rules step with alternating rules that keep the stored background dead
+        let (rules, inverted) = rules.b0_emulation(self.inverted);
+        let rules = &rules;
+        if self.last_rules.as_ref() != Some(rules) {
+            self.last_rules = Some(rules.clone());
+            self.changed_tiles.fill(true);
+        }
+        // Also drops planes left over from editing in dying cells under other rules
+        self.set_planes(planes_for_age(rules.states - 2));
+        if rules.wireworld {
+            self.step_wireworld();
+        } else if let Some(table) = &rules.margolus {
+            self.step_margolus(table);
+            self.odd_blocks = !self.odd_blocks;
+        } else if rules.is_larger_than_life() {
+            self.step_larger_than_life(rules);
+        } else {
+            self.step_life_like(rules);
+        }
+
+        if self.planes > 0 && !rules.wireworld {
+            self.age_dying_cells(rules.states);
+        }
+        std::mem::swap(&mut self.cells, &mut self.next_cells);
+        std::mem::swap(&mut self.dying, &mut self.next_dying);
+        std::mem::swap(&mut self.changed_tiles, &mut self.next_changed_tiles);
+        self.inverted = inverted;
+    }
+
+    /// Writes the next generation of live cells for rules over the 3x3 neighborhood, 64 cells at a time and
+    /// skipping quiet tiles
+    fn step_life_like(&mut self, rules: &Rules) {
+        let birth = count_mask(&rules.birth_counts);
+        let survival = count_mask(&rules.survival_counts);
+        let neighborhood = rules.neighborhood;
+        let transitions = rules.isotropic.is_some().then(|| rules.transition_table());
+        self.fill_halo();
+
+        let stride = self.stride;
+        let width = self.width;
+        let height = self.height;
+        let planes = self.planes;
+        let cells = &self.cells;
+        let dying = &self.dying;
+        let changed_tiles = &self.changed_tiles;
+
+        // A tile can only change if something in it or right next to it did
+        let tile_rows = changed_tiles.len() / stride;
+        let is_active = |tile_x: usize, tile_y: usize| {
+            (tile_y.saturating_sub(1)..(tile_y + 2).min(tile_rows)).any(|tile_y| {
+                (tile_x.saturating_sub(1)..(tile_x + 2).min(stride))
+                    .any(|tile_x| changed_tiles[tile_y * stride + tile_x])
+            })
+        };
+
+        // Each output row only reads the padded rows above, at and below it, so bands of tiles are independent.
+        // Words of inactive tiles are left alone: the buffer still holds the previous generation, which they
+        // didn't change from.
+        let range = stride..stride * (height + 1);
+        self.next_cells[range]
+            .par_chunks_mut(stride * TILE_ROWS)
+            .zip(self.next_changed_tiles.par_chunks_mut(stride))
+            .enumerate()
+            .for_each(|(tile_y, (next_band, next_changed))| {
+                for (tile_x, changed) in next_changed.iter_mut().enumerate() {
+                    *changed = false;
+                    if !is_active(tile_x, tile_y) {
+                        continue;
+                    }
+                    let i = tile_x;
+                    for (row_in_band, next_row) in next_band.chunks_exact_mut(stride).enumerate() {
+                        let y = tile_y * TILE_ROWS + row_in_band;
+                        let below = &cells[y * stride..(y + 1) * stride];
+                        let row = &cells[(y + 1) * stride..(y + 2) * stride];
+                        let above = &cells[(y + 2) * stride..(y + 3) * stride];
+                        let alive = row[i];
+                        let next = match &transitions {
+                            // Isotropic non-totalistic rules depend on where the neighbors are, not just how many
+                            Some(transitions) => lookup_transitions(
+                                transitions,
+                                &[
+                                    shifted_left(below, i),
+                                    below[i],
+                                    shifted_right(below, i),
+                                    shifted_left(row, i),
+                                    alive,
+                                    shifted_right(row, i),
+                                    shifted_left(above, i),
+                                    above[i],
+                                    shifted_right(above, i),
+                                ],
+                            ),
+                            None => {
+                                let counts = match neighborhood {
+                                    Neighborhood::Moore => NeighborCounts::sum(&[
+                                        shifted_left(below, i),
+                                        below[i],
+                                        shifted_right(below, i),
+                                        shifted_left(row, i),
+                                        shifted_right(row, i),
+                                        shifted_left(above, i),
+                                        above[i],
+                                        shifted_right(above, i),
+                                    ]),
+                                    Neighborhood::VonNeumann => NeighborCounts::sum(&[
+                                        below[i],
+                                        shifted_left(row, i),
+                                        shifted_right(row, i),
+                                        above[i],
+                                    ]),
+                                    // Rows are sheared left as they go up, so the hexagon takes the left cell
+                                    // below and the right cell above
+                                    Neighborhood::Hexagonal => NeighborCounts::sum(&[
+                                        shifted_left(below, i),
+                                        below[i],
+                                        shifted_left(row, i),
+                                        shifted_right(row, i),
+                                        above[i],
+                                        shifted_right(above, i),
+                                    ]),
+                                };
+                                alive & counts.matching(survival) | !alive & counts.matching(birth)
+                            }
+                        };
+                        let mask = cell_mask(width, i);
+                        // Dying cells neither count as neighbors nor can be born into
+                        let word = (y + 1) * stride + i;
+                        let dying = dying[word * planes..(word + 1) * planes]
+                            .iter()
+                            .fold(0, |any, plane| any | plane);
+                        let next = next & !dying & mask;
+                        *changed |= ((next ^ alive) | dying) & mask != 0;
+                        next_row[i] = next;
+                    }
+                }
+            });
+    }
+
+    /// Writes the next generation of live cells for Larger than Life rules. Live cells are first copied into a
+    /// summed-area table, padded by the range on every side through the topology, so the count over any box of cells
+    /// takes four lookups whatever the range: one box per cell for the Moore neighborhood, or one row of the diamond or
+    /// hexagon at a time for von Neumann and hexagonal ones. Every tile is treated as changed, since a change reaches
+    /// further than the neighboring tiles.
+    fn step_larger_than_life(&mut self, rules: &Rules) {
+        let range = rules.range as isize;
+        let (width, height) = (self.width, self.height);
+        let (padded_width, padded_height) = (width + 2 * rules.range, height + 2 * rules.range);
+
+        // `table[y * (padded_width + 1) + x]` counts live cells in the padded columns below x and rows below y
+        let columns = padded_width + 1;
+        let mut table = vec![0u32; columns * (padded_height + 1)];
+        for y in 0..padded_height {
+            let mut row_sum = 0;
+            for x in 0..padded_width {
+                let is_alive = self
+                    .wrap(x as isize - range, y as isize - range)
+                    .map_or(self.inverted, |(x, y)| self.is_alive(x, y));
+                row_sum += is_alive as u32;
+                table[(y + 1) * columns + x + 1] = table[y * columns + x + 1] + row_sum;
+            }
+        }
+        // Live cells in padded columns `x0..x1` of padded rows `y0..y1`
+        let box_sum = |x0: usize, y0: usize, x1: usize, y1: usize| {
+            table[y1 * columns + x1] + table[y0 * columns + x0] - table[y0 * columns + x1] - table[y1 * columns + x0]
+        };
+
+        let lookup = |counts: &[usize]| {
+            let mut matches = vec![false; rules.neighborhood.max_neighbors(rules.range) + 2];
+            for &count in counts {
+                matches[count] = true;
+            }
+            matches
+        };
+        let (birth, survival) = (lookup(&rules.birth_counts), lookup(&rules.survival_counts));
+
+        let (stride, planes) = (self.stride, self.planes);
+        let (cells, dying) = (&self.cells, &self.dying);
+        self.next_cells
+            .par_chunks_mut(stride)
+            .enumerate()
+            .for_each(|(padded_y, next_row)| {
+                next_row.fill(0);
+                if padded_y == 0 || padded_y > height {
+                    return; // The halo rows are filled in before each life-like step
+                }
+                let y = padded_y - 1;
+                for x in 0..width {
+                    // The cell sits at padded position (x + range, y + range)
+                    let r = rules.range;
+                    let mut count = match rules.neighborhood {
+                        Neighborhood::Moore => box_sum(x, y, x + 2 * r + 1, y + 2 * r + 1),
+                        Neighborhood::VonNeumann => (0..=2 * r)
+                            .map(|row| {
+                                let reach = r - row.abs_diff(r);
+                                box_sum(x + r - reach, y + row, x + r + reach + 1, y + row + 1)
+                            })
+                            .sum(),
+                        // Each row of the hexagon is shifted by half its distance from the center
+                        Neighborhood::Hexagonal => (0..=2 * r)
+                            .map(|row| {
+                                box_sum(
+                                    x + row.saturating_sub(r),
+                                    y + row,
+                                    x + (row + r).min(2 * r) + 1,
+                                    y + row + 1,
+                                )
+                            })
+                            .sum(),
+                    } as usize;
+
+                    let column = x + 1;
+                    let (word, bit) = (padded_y * stride + column / 64, 1 << (column % 64));
+                    let is_alive = cells[word] & bit != 0;
+                    let is_dying = dying[word * planes..(word + 1) * planes]
+                        .iter()
+                        .any(|plane| plane & bit != 0);
+                    if is_alive && !rules.include_center {
+                        count -= 1;
+                    }
+                    let next = if is_alive {
+                        survival[count]
+                    } else {
+                        !is_dying && birth[count]
+                    };
+                    if next {
+                        next_row[column / 64] |= bit;
+                    }
+                }
+            });
+        self.next_changed_tiles.fill(true);
+    }
+
+    /// Writes the next generation for Margolus block rules, a pair of rows at a time. Each block's four cells are
+    /// lined up on the bit of its left column, so every word holds 32 blocks, and each cell of the next block is
+    /// the union of the table entries that fill it. Blocks hanging off the edge reach into the halo. Every tile is
+    /// treated as changed, since the blocks shift every generation.
+    fn step_margolus(&mut self, table: &[u8; 16]) {
+        self.fill_halo();
+        let (stride, width) = (self.stride, self.width);
+        // Padded rows and columns are one past the cells, so even blocks start at odd padded positions
+        let (left_columns, first_row) = if self.odd_blocks {
+            (0x5555_5555_5555_5555, 0)
+        } else {
+            (0xaaaa_aaaa_aaaa_aaaa, 1)
+        };
+        // One more pair than the rows hold, for when the first pair starts at the halo row
+        self.next_blocks.resize(stride * (self.height / 2 + 2), [0; 4]);
+        let cells = &self.cells;
+        self.next_cells[first_row * stride..]
+            .par_chunks_mut(2 * stride)
+            .zip(self.next_blocks.par_chunks_mut(stride))
+            .enumerate()
+            .for_each(|(pair, (next_rows, next_blocks))| {
+                if next_rows.len() < 2 * stride {
+                    return; // A halo row left over past the last pair
+                }
+                let y = first_row + 2 * pair;
+                let (lower, upper) = (
+                    &cells[y * stride..(y + 1) * stride],
+                    &cells[(y + 1) * stride..(y + 2) * stride],
+                );
+                // The next top-left, top-right, bottom-left and bottom-right cells of the blocks in each word
+                for (i, next) in next_blocks.iter_mut().enumerate() {
+                    let block = [upper[i], shifted_right(upper, i), lower[i], shifted_right(lower, i)];
+                    *next = [0; 4];
+                    for (contents, &next_contents) in table.iter().enumerate() {
+                        let matching = (0..4).fold(left_columns, |matching, k| {
+                            matching & if contents & 1 << k != 0 { block[k] } else { !block[k] }
+                        });
+                        for (k, next) in next.iter_mut().enumerate() {
+                            if next_contents & 1 << k != 0 {
+                                *next |= matching;
+                            }
+                        }
+                    }
+                }
+                let (next_lower, next_upper) = next_rows.split_at_mut(stride);
+                for i in 0..stride {
+                    // Right-hand cells move back over one column, carrying across from the word before
+                    let right = |k: usize| next_blocks[i][k] << 1 | if i > 0 { next_blocks[i - 1][k] >> 63 } else { 0 };
+                    next_upper[i] = (next_blocks[i][0] | right(1)) & cell_mask(width, i);
+                    next_lower[i] = (next_blocks[i][2] | right(3)) & cell_mask(width, i);
+                }
+            });
+        self.next_changed_tiles.fill(true);
+    }
+
+    /// Writes the next generation under Wireworld, 64 cells at a time. Electron heads are the live cells, and tails
+    /// and conductor are the first and second dying states, so only heads are copied into the halo and counted.
+    /// Every tile is treated as changed, since electrons keep moving along wires that look quiet.
+    fn step_wireworld(&mut self) {
+        self.fill_halo();
+        let (stride, width, height) = (self.stride, self.width, self.height);
+        let (cells, dying) = (&self.cells, &self.dying);
+        self.next_cells
+            .par_chunks_mut(stride)
+            .zip(self.next_dying.par_chunks_mut(stride * 2))
+            .enumerate()
+            .for_each(|(y, (next_row, next_ages))| {
+                if y == 0 || y > height {
+                    return;
+                }
+                let below = &cells[(y - 1) * stride..y * stride];
+                let row = &cells[y * stride..(y + 1) * stride];
+                let above = &cells[(y + 1) * stride..(y + 2) * stride];
+                for i in 0..stride {
+                    let heads = NeighborCounts::sum(&[
+                        shifted_left(below, i),
+                        below[i],
+                        shifted_right(below, i),
+                        shifted_left(row, i),
+                        shifted_right(row, i),
+                        shifted_left(above, i),
+                        above[i],
+                        shifted_right(above, i),
+                    ]);
+                    let word = y * stride + i;
+                    let (tails, conductor) = (dying[word * 2], dying[word * 2 + 1]);
+                    // Conductor next to one or two electron heads becomes a head itself
+                    let sparks = conductor & heads.matching(0b110);
+                    let mask = cell_mask(width, i);
+                    next_row[i] = sparks & mask;
+                    next_ages[i * 2] = row[i] & mask;
+                    next_ages[i * 2 + 1] = (tails | conductor & !sparks) & mask;
+                }
+            });
+        self.next_changed_tiles.fill(true);
+    }
+
+    /// Writes the next generation's ages: dying cells get a generation older until they pass the last of the
+    /// `states`, and live cells that didn't survive into `next_cells` start dying. Every word is rewritten, since
+    /// cells can finish dying in tiles that then go quiet.
+    fn age_dying_cells(&mut self, states: usize) {
+        let (stride, width, height, planes) = (self.stride, self.width, self.height, self.planes);
+        let (cells, next_cells, dying) = (&self.cells, &self.next_cells, &self.dying);
+        let last_age = states - 2;
+        self.next_dying
+            .par_chunks_mut(stride * planes)
+            .enumerate()
+            .for_each(|(y, next_row)| {
+                if y == 0 || y > height {
+                    return; // The halo rows never hold dying cells
+                }
+                for i in 0..stride {
+                    let word = y * stride + i;
+                    let ages = &dying[word * planes..(word + 1) * planes];
+                    let next_ages = &mut next_row[i * planes..(i + 1) * planes];
+
+                    // Add one to every dying cell's age, rippling the carry up through the planes
+                    let mut carry = ages.iter().fold(0, |any, plane| any | plane);
+                    for (next_age, &age) in next_ages.iter_mut().zip(ages) {
+                        *next_age = age ^ carry;
+                        carry &= age;
+                    }
+                    // Cells past the last dying state are dead
+                    let finished = next_ages.iter().enumerate().fold(!0, |equal, (k, &plane)| {
+                        equal & if (last_age + 1) & 1 << k != 0 { plane } else { !plane }
+                    });
+                    let started = cells[word] & !next_cells[word] & cell_mask(width, i);
+                    for (k, next_age) in next_ages.iter_mut().enumerate() {
+                        *next_age &= !finished;
+                        if k == 0 {
+                            *next_age |= started; // Age 1, the first dying state
+                        }
+                    }
+                }
+            });
+    }
+
+    /// Copies the cells across each edge into the halo around the grid, following the topology. Edge cells
+    /// whose halo copy changed have their tiles marked, since wrapping makes them neighbors of the cells that
+    /// changed on the far side.
+    fn fill_halo(&mut self) {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let mut halo_index = 0;
+        let mut copy = |grid: &mut Grid, x: isize, y: isize| {
+            let is_alive = grid.copy_into_halo(x, y);
+            if grid.halo[halo_index] != is_alive {
+                grid.halo[halo_index] = is_alive;
+                grid.mark_changed(x.clamp(0, width - 1) as usize, y.clamp(0, height - 1) as usize);
+            }
+            halo_index += 1;
+        };
+        for x in -1..=width {
+            copy(self, x, -1);
+            copy(self, x, height);
+        }
+        for y in 0..height {
+            copy(self, -1, y);
+            copy(self, width, y);
+        }
+    }
+
+    /// Sets the halo bit for off-grid position `(x, y)` from the cell the topology maps it to, returning it
+    fn copy_into_halo(&mut self, x: isize, y: isize) -> bool {
+        let is_alive = self.wrap(x, y).map_or(self.inverted, |(x, y)| self.is_alive(x, y));
+        let column = (x + 1) as usize;
+        let word = (y + 1) as usize * self.stride + column / 64;
+        let bit = 1 << (column % 64);
+        if is_alive {
+            self.cells[word] |= bit;
+        } else {
+            self.cells[word] &= !bit;
+        }
+        is_alive
+    }
+}
+
+impl Board for Grid {
+    fn place_cell(&mut self, x: isize, y: isize) -> bool {
+        match self.wrap(x, y) {
+            Some((x, y)) => {
+                self.set_alive(x, y, true);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn place_state(&mut self, x: isize, y: isize, state: u8) -> bool {
+        match self.wrap(x, y) {
+            Some((x, y)) => {
+                self.set_state(x, y, state);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Number of bit planes needed to count dying ages up to `age` and one past it, which is where they wrap to dead
+fn planes_for_age(age: usize) -> usize {
+    if age == 0 {
+        0
+    } else {
+        (usize::BITS - (age + 1).leading_zeros()) as usize
+    }
+}
+
+/// The bits of word `i` in a padded row of a `width`-cell grid that hold cells rather than halo or padding
+fn cell_mask(width: usize, i: usize) -> u64 {
+    let first = (i * 64).max(1) - i * 64;
+    let end = (width + 1).min(i * 64 + 64).saturating_sub(i * 64);
+    if first >= end {
+        return 0;
+    }
+    let below_end = if end == 64 { !0 } else { (1 << end) - 1 };
+    below_end & !((1 << first) - 1)
+}
+
+/// Word `i` of `row` with each bit replaced by its left-hand neighbor's
+fn shifted_left(row: &[u64], i: usize) -> u64 {
+    let carry = if i > 0 { row[i - 1] >> 63 } else { 0 };
+    row[i] << 1 | carry
+}
+
+/// Word `i` of `row` with each bit replaced by its right-hand neighbor's
+fn shifted_right(row: &[u64], i: usize) -> u64 {
+    let carry = if i + 1 < row.len() { row[i + 1] << 63 } else { 0 };
+    row[i] >> 1 | carry
+}
+
+/// A bit set of neighbor counts, with bit `n` set when `n` neighbors match the rule
+pub(crate) fn count_mask(counts: &[usize]) -> u16 {
+    counts.iter().fold(0, |mask, &count| mask | 1 << count)
+}
+
+/// Next states of 64 cells at once from a rule's `Rules::transition_table`, one table lookup per cell that has a live
+/// cell around it. `block` holds the words of each cell's 3x3 block in the table's bit order, from the bottom-left
+/// neighbor to the top-right one.
+pub(crate) fn lookup_transitions(transitions: &[bool; 512], block: &[u64; 9]) -> u64 {
+    // Cells with nothing around them all take the empty block's entry, so only the rest need a lookup
+    let occupied = block.iter().fold(0, |occupied, word| occupied | word);
+    let mut next = if transitions[0] { !occupied } else { 0 };
+    let mut cells = occupied;
+    while cells != 0 {
+        let bit = cells.trailing_zeros();
+        cells &= cells - 1;
+        let index = block
+            .iter()
+            .enumerate()
+            .fold(0, |index, (k, word)| index | ((word >> bit) & 1) << k);
+        next |= (transitions[index as usize] as u64) << bit;
+    }
+    next
+}
+
+/// Neighbor counts for 64 cells at once, stored bit-sliced: bit `i` of `bits[k]` is bit `k` of cell `i`'s count
+pub(crate) struct NeighborCounts {
+    bits: [u64; 4],
+}
+
+impl NeighborCounts {
+    /// Adds up to 15 words of neighbor bits lane by lane, rippling carries through the count bits
+    pub(crate) fn sum(neighbors: &[u64]) -> Self {
+        let mut bits = [0; 4];
+        for &neighbor in neighbors {
+            let mut carry = neighbor;
+            for bit in &mut bits {
+                let next_carry = *bit & carry;
+                *bit ^= carry;
+                carry = next_carry;
+            }
+        }
+        Self { bits }
+    }
+
+    /// Lanes whose count is one of those set in `counts`
+    pub(crate) fn matching(&self, counts: u16) -> u64 {
+        (0..16)
+            .filter(|count| counts & 1 << count != 0)
+            .fold(0, |matches, count| {
+                let equal = self.bits.iter().enumerate().fold(!0, |equal, (k, &bit)| {
+                    equal & if count & 1 << k != 0 { bit } else { !bit }
+                });
+                matches | equal
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::{Orientation, Pattern};
+    use crate::rules::{RuleSet, CONDUCTOR, ELECTRON_HEAD, ELECTRON_TAIL};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const TOPOLOGIES: [Topology; 5] = [
+        Topology::Torus,
+        Topology::Bounded,
+        Topology::KleinBottle,
+        Topology::CrossSurface,
+        Topology::Sphere,
+    ];
+
+    /// State of every cell, row by row
+    fn states(grid: &Grid) -> Vec<u8> {
+        (0..grid.height)
+            .flat_map(|y| (0..grid.width).map(move |x| grid.state(x, y)))
+            .collect()
+    }
+
+    fn set_states(grid: &mut Grid, states: &[u8]) {
+        for (i, &state) in states.iter().enumerate() {
+            grid.set_state(i % grid.width, i / grid.width, state);
+        }
+    }
+
+    /// A grid whose cells take random states from `choices`
+    fn random_grid(size: usize, topology: Topology, choices: &[u8], rng: &mut StdRng) -> Grid {
+        let mut grid = Grid::new(size, size);
+        grid.set_topology(topology);
+        let states: Vec<_> = (0..size * size)
+            .map(|_| choices[rng.gen_range(0..choices.len())])
+            .collect();
+        set_states(&mut grid, &states);
+        grid
+    }
+
+    /// The next state of every cell, found by visiting each neighbor of each cell in turn
+    fn reference_step(grid: &Grid, rules: &Rules) -> Vec<u8> {
+        let alive = |x: isize, y: isize| grid.wrap(x, y).is_some_and(|(x, y)| grid.state(x, y) == 1);
+        let table = rules.transition_table();
+        let range = rules.range as isize;
+        (0..grid.width * grid.height)
+            .map(|i| {
+                let (x, y) = ((i % grid.width) as isize, (i / grid.width) as isize);
+                let state = grid.state(x as usize, y as usize);
+                let mut block = 0;
+                let mut count = 0;
+                for dy in -range..=range {
+                    for dx in -range..=range {
+                        let inside = match rules.neighborhood {
+                            Neighborhood::Moore => true,
+                            Neighborhood::VonNeumann => dx.abs() + dy.abs() <= range,
+                            Neighborhood::Hexagonal => dx.abs().max(dy.abs()).max((dx - dy).abs()) <= range,
+                        };
+                        if alive(x + dx, y + dy) {
+                            if range == 1 {
+                                block |= 1 << (3 * (dy + 1) + dx + 1);
+                            }
+                            if inside && ((dx, dy) != (0, 0) || rules.include_center) {
+                                count += 1;
+                            }
+                        }
+                    }
+                }
+                if rules.wireworld {
+                    return match state {
+                        ELECTRON_HEAD => ELECTRON_TAIL,
+                        ELECTRON_TAIL => CONDUCTOR,
+                        CONDUCTOR if count == 1 || count == 2 => ELECTRON_HEAD,
+                        state => state,
+                    };
+                }
+                let lives = if rules.isotropic.is_some() {
+                    table[block]
+                } else if state == 1 {
+                    rules.survival_counts.contains(&count)
+                } else {
+                    rules.birth_counts.contains(&count)
+                };
+                match state {
+                    0 | 1 if lives => 1,
+                    0 => 0,
+                    1 if rules.states > 2 => 2,
+                    1 => 0,
+                    dying if dying as usize + 1 == rules.states => 0,
+                    dying => dying + 1,
+                }
+            })
+            .collect()
+    }
+
+    /// The next generation of a Margolus rule, turning each block whose bottom-left cell has odd coordinates when
+    /// `odd_blocks` is set or even ones otherwise
+    fn reference_margolus_step(grid: &Grid, table: &[u8; 16], odd_blocks: bool) -> Vec<u8> {
+        let alive = |x: isize, y: isize| grid.wrap(x, y).is_some_and(|(x, y)| grid.state(x, y) == 1);
+        (0..grid.width * grid.height)
+            .map(|i| {
+                let (x, y) = ((i % grid.width) as isize, (i / grid.width) as isize);
+                let (left, bottom) = (
+                    x - (x - odd_blocks as isize).rem_euclid(2),
+                    y - (y - odd_blocks as isize).rem_euclid(2),
+                );
+                let block = alive(left, bottom + 1) as usize
+                    | (alive(left + 1, bottom + 1) as usize) << 1
+                    | (alive(left, bottom) as usize) << 2
+                    | (alive(left + 1, bottom) as usize) << 3;
+                let bit = if y == bottom { 2 } else { 0 } + (x - left);
+                table[block] >> bit & 1
+            })
+            .collect()
+    }
+
+    /// Cells as they really are, with an inverted generation turned back
+    fn actual_states(grid: &Grid) -> Vec<u8> {
+        states(grid)
+            .into_iter()
+            .map(|state| if grid.inverted && state < 2 { 1 - state } else { state })
+            .collect()
+    }
+
+    #[test]
+    fn steps_like_reference() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for rulestring in [
+            "B3/S23",
+            "B36/S23",
+            "B2/S013V",
+            "B2/S34H",
+            "B2-a/S12",
+            "B2ce3-q/S1c23-ak",
+            "B2/S/C3",
+            "345/2/4",
+            "B2/S34/C5H",
+            "R2,C0,M1,S3..6,B3..4,NM",
+            "R2,C3,M0,S2..5,B3..4,NN",
+            "R2,C0,M0,S4..8,B5..7,NH",
+            "WireWorld",
+        ] {
+            let rules: Rules = rulestring.parse().unwrap();
+            let choices: &[u8] = if rules.wireworld {
+                &[0, 0, CONDUCTOR, CONDUCTOR, CONDUCTOR, ELECTRON_HEAD, ELECTRON_TAIL]
+            } else if rules.states > 3 {
+                &[0, 0, 0, 1, 2, 3]
+            } else if rules.states > 2 {
+                &[0, 0, 0, 1, 2]
+            } else {
+                &[0, 0, 1]
+            };
+            for topology in TOPOLOGIES {
+                // Wider than a word, so rows span several words and tiles
+                let mut grid = random_grid(70, topology, choices, &mut rng);
+                for generation in 0..8 {
+                    let expected = reference_step(&grid, &rules);
+                    grid.step(&rules);
+                    assert!(
+                        states(&grid) == expected,
+                        "{rulestring} on {topology:?}, generation {generation}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn blinker_oscillates_and_glider_travels() {
+        let rules = RuleSet::Conway.to_rules();
+        let mut grid = Grid::new(16, 16);
+        Pattern::Blinker.add_to_grid(&mut grid, &[], Orientation::default(), 3, 3);
+        let blinker = states(&grid);
+        grid.step(&rules);
+        assert_ne!(states(&grid), blinker);
+        grid.step(&rules);
+        assert_eq!(states(&grid), blinker);
+
+        // A glider crosses the torus diagonally and comes back where it started
+        let mut grid = Grid::new(16, 16);
+        Pattern::Glider.add_to_grid(&mut grid, &[], Orientation::default(), 14, 14);
+        let glider = states(&grid);
+        for _ in 0..4 * 16 - 1 {
+            grid.step(&rules);
+            assert_eq!(grid.population(), 5);
+            assert_ne!(states(&grid), glider);
+        }
+        grid.step(&rules);
+        assert_eq!(states(&grid), glider);
+    }
+
+    #[test]
+    fn glider_survives_crossing_joined_edges() {
+        let rules = RuleSet::Conway.to_rules();
+        for topology in TOPOLOGIES.into_iter().filter(|&topology| topology != Topology::Bounded) {
+            let mut grid = Grid::new(16, 16);
+            grid.set_topology(topology);
+            // Off the diagonal, so the glider misses the corners, where a twisted edge doesn't line up with its
+            // neighbors
+            Pattern::Glider.add_to_grid(&mut grid, &[], Orientation::default(), 3, 9);
+            for generation in 0..4 * 16 * 2 {
+                grid.step(&rules);
+                assert_eq!(grid.population(), 5, "{topology:?}, generation {generation}");
+            }
+        }
+    }
+
+    #[test]
+    fn brians_brain_spaceship_travels() {
+        let rules = RuleSet::BriansBrain.to_rules();
+        let mut grid = Grid::new(20, 20);
+        grid.set_topology(Topology::Bounded);
+        for x in 5..7 {
+            grid.set_state(x, 2, 2);
+            grid.set_state(x, 3, 1);
+        }
+        for generation in 1..=10 {
+            grid.step(&rules);
+            for x in 5..7 {
+                assert_eq!(grid.state(x, 2 + generation), 2);
+                assert_eq!(grid.state(x, 3 + generation), 1);
+            }
+            assert_eq!(states(&grid).iter().filter(|&&state| state != 0).count(), 4);
+        }
+    }
+
+    #[test]
+    fn electron_runs_along_wire() {
+        let rules = RuleSet::Wireworld.to_rules();
+        let mut grid = Grid::new(20, 5);
+        grid.set_topology(Topology::Bounded);
+        for x in 0..20 {
+            grid.set_state(x, 2, CONDUCTOR);
+        }
+        grid.set_state(0, 2, ELECTRON_TAIL);
+        grid.set_state(1, 2, ELECTRON_HEAD);
+        for generation in 1..=18 {
+            grid.step(&rules);
+            assert_eq!(grid.state(generation, 2), ELECTRON_TAIL);
+            assert_eq!(grid.state(generation + 1, 2), ELECTRON_HEAD);
+            assert_eq!(grid.population(), 1);
+        }
+    }
+
+    #[test]
+    fn margolus_steps_like_reference() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for rule_set in [
+            RuleSet::Critters,
+            RuleSet::Tron,
+            RuleSet::BilliardBallMachine,
+            RuleSet::SingleRotation,
+        ] {
+            let rules = rule_set.to_rules();
+            let table = rules.margolus.unwrap();
+            for (topology, width, height) in [
+                (Topology::Torus, 130, 64),
+                (Topology::Bounded, 131, 67),
+                (Topology::Bounded, 128, 66),
+            ] {
+                let mut grid = Grid::random(width, height, 0.2, &mut rng);
+                grid.set_topology(topology);
+                let mut actual = grid.clone();
+                for generation in 0..20 {
+                    let odd_blocks = grid.odd_blocks();
+                    let next = reference_margolus_step(&actual, &table, odd_blocks);
+                    set_states(&mut actual, &next);
+                    grid.step(&rules);
+                    assert_eq!(grid.odd_blocks(), !odd_blocks);
+                    assert!(
+                        actual_states(&grid) == states(&actual),
+                        "{rule_set:?} on {topology:?} {width}x{height}, generation {generation}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn billiard_ball_travels_diagonally() {
+        let rules = RuleSet::BilliardBallMachine.to_rules();
+        let mut grid = Grid::new(20, 20);
+        grid.set_alive(10, 10, true);
+        for generation in 1..=8 {
+            grid.step(&rules);
+            assert!(grid.is_alive(10 + generation, 10 + generation));
+            assert_eq!(grid.population(), 1);
+        }
+    }
+
+    #[test]
+    fn b0_rules_step_the_actual_cells() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for rulestrings in [
+            &["B0123478/S34678"][..],
+            &["B0/S8"],
+            &["B0/S"],
+            &["B013/S012V"],
+            &["B0123/S0123H"],
+            &["B04-ak/S4-anz"],
+            &["R2,C0,M1,S0..3,B0..4,NM"],
+            &["B0123478/S34678", "B3/S23", "B0/S8", "B36/S238", "B0/S"],
+        ] {
+            for topology in [Topology::Torus, Topology::Bounded] {
+                let mut grid = random_grid(70, topology, &[0, 0, 1], &mut rng);
+                let mut actual = grid.clone();
+                for generation in 0..30 {
+                    let rules: Rules = rulestrings[generation / 7 % rulestrings.len()].parse().unwrap();
+                    let next = reference_step(&actual, &rules);
+                    set_states(&mut actual, &next);
+                    grid.step(&rules);
+                    assert!(
+                        actual_states(&grid) == states(&actual),
+                        "{rulestrings:?} on {topology:?}, generation {generation}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn inverted_generation_turns_back_for_multi_state_rules() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let b0: Rules = "B0123478/S34678".parse().unwrap();
+        for rules in [RuleSet::BriansBrain.to_rules(), RuleSet::Wireworld.to_rules()] {
+            let mut grid = random_grid(70, Topology::Torus, &[0, 0, 1], &mut rng);
+            grid.step(&b0);
+            assert!(grid.is_inverted());
+            let mut actual = grid.clone();
+            actual.inverted = false;
+            set_states(&mut actual, &actual_states(&grid));
+
+            grid.step(&rules);
+            assert!(!grid.is_inverted());
+            assert_eq!(states(&grid), reference_step(&actual, &rules));
+        }
+    }
+}
@@ -0,0 +1,503 @@
+use crate::patterns::Board;
+use crate::rules::Rules;
+use std::collections::HashMap;
+
+/// Index of a node in `HashLife::nodes`
+type NodeId = u32;
+
+/// The two level 0 nodes: single cells
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+/// Positions of the quadrants in `Node::children`, with north towards +y
+const NW: usize = 0;
+const NE: usize = 1;
+const SW: usize = 2;
+const SE: usize = 3;
+
+/// Node count above which unreachable nodes and memoized results are dropped before the next step
+const GARBAGE_COLLECTION_THRESHOLD: usize = 1 << 22;
+
+/// A square of 2^level x 2^level cells, built from four quadrants of the level below
+#[derive(Clone, Copy)]
+struct Node {
+    level: u8,
+    children: [NodeId; 4],
+    population: u64,
+}
+
+/// An unbounded universe stored as a hash-consed quadtree, stepped with Gosper's HashLife algorithm.
+///
+/// Identical regions share a single node and the future of each node is memoized, so repetitive patterns such
+/// as guns and breeders can be advanced by 2^k generations at a time at a cost that grows far slower than the
+/// number of cells or generations. Only two-state 3x3 rules without B0 are supported, since B0 would bring the
+/// infinite empty background to life.
+pub struct HashLife {
+    nodes: Vec<Node>,
+    /// Finds the existing node for a set of quadrants, so each distinct square is stored once
+    index: HashMap<[NodeId; 4], NodeId>,
+    /// The empty node of each level
+    empty: Vec<NodeId>,
+    /// The center of each node advanced by `step_log2` generations (or half its size, if smaller)
+    results: HashMap<NodeId, NodeId>,
+    /// `Rules::transition_table` of the rules and the step size that `results` were computed with
+    transitions: Option<Box<[bool; 512]>>,
+    step_log2: u32,
+    /// Covers cells from -2^(level-1) to 2^(level-1) - 1 on both axes
+    root: NodeId,
+}
+
+impl Default for HashLife {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HashLife {
+    /// Creates an empty universe
+    pub fn new() -> Self {
+        let leaf = |population| Node {
+            level: 0,
+            children: [DEAD; 4],
+            population,
+        };
+        let mut hashlife = Self {
+            nodes: vec![leaf(0), leaf(1)],
+            index: HashMap::new(),
+            empty: vec![DEAD],
+            results: HashMap::new(),
+            transitions: None,
+            step_log2: 0,
+            root: DEAD,
+        };
+        hashlife.root = hashlife.empty_node(3);
+        hashlife
+    }
+
+    /// Whether the rules have only live and dead cells and count neighbors over the 3x3 neighborhood, and can be
+    /// run without bringing the empty background to life
+    pub fn supports(rules: &Rules) -> bool {
+        rules.states == 2
+            && !rules.is_larger_than_life()
+            && rules.margolus.is_none()
+            && !rules.birth_counts.contains(&0)
+    }
+
+    pub fn is_alive(&self, x: i64, y: i64) -> bool {
+        if !self.contains(x, y) {
+            return false;
+        }
+        let half = self.half_width();
+        let (mut node, mut x, mut y) = (self.root, x + half, y + half);
+        for level in (1..=self.level()).rev() {
+            let half = 1 << (level - 1);
+            node = self.nodes[node as usize].children[quadrant(x >= half, y >= half)];
+            x %= half;
+            y %= half;
+        }
+        node == ALIVE
+    }
+
+    /// Sets a cell, growing the universe to reach it if needed
+    pub fn set_alive(&mut self, x: i64, y: i64, is_alive: bool) {
+        while !self.contains(x, y) {
+            self.expand();
+        }
+        let half = self.half_width();
+        self.root = self.set_in(self.root, x + half, y + half, is_alive);
+    }
+
+    /// Number of live cells
+    pub fn population(&self) -> u64 {
+        self.nodes[self.root as usize].population
+    }
+
+    /// Makes every cell dead
+    pub fn clear(&mut self) {
+        self.root = self.empty_node(3);
+    }
+
+    /// Advances the universe by 2^`step_log2` generations.
+    ///
+    /// Panics if the rules contain B0, dying states or a larger neighborhood; check them with `HashLife::supports`
+    /// first.
+    pub fn step(&mut self, rules: &Rules, step_log2: u32) {
+        assert!(
+            Self::supports(rules),
+            "HashLife only runs two-state 3x3 rules without B0"
+        );
+        let transitions = rules.transition_table();
+        if self.transitions.as_deref() != Some(&transitions) || self.step_log2 != step_log2 {
+            self.transitions = Some(Box::new(transitions));
+            self.step_log2 = step_log2;
+            self.results.clear();
+        }
+        if self.nodes.len() > GARBAGE_COLLECTION_THRESHOLD {
+            self.collect_garbage();
+        }
+
+        // Pad the universe so nothing can escape the center half that the step returns
+        while self.level() < step_log2 + 3 || !self.is_padded() {
+            self.expand();
+        }
+        self.root = self.advance(self.root);
+    }
+
+    /// Calls `f(column, row)` for every block of 2^`scale_log2` x 2^`scale_log2` cells containing a live cell,
+    /// within the `columns` x `rows` blocks whose bottom-left cell is `(x, y)`. `x` and `y` must be multiples of
+    /// the block size, so each node at or below that size falls into exactly one block.
+    pub fn for_each_live_block(
+        &self,
+        x: i64,
+        y: i64,
+        columns: usize,
+        rows: usize,
+        scale_log2: u32,
+        mut f: impl FnMut(usize, usize),
+    ) {
+        let window = Window {
+            x,
+            y,
+            width: (columns as i64) << scale_log2,
+            height: (rows as i64) << scale_log2,
+            scale_log2,
+        };
+        let half = self.half_width();
+        self.visit_live_blocks(self.root, -half, -half, &window, &mut f);
+    }
+
+    /// Smallest and largest coordinates of live cells, as `(min_x, min_y, max_x, max_y)`
+    pub fn live_bounds(&self) -> Option<(i64, i64, i64, i64)> {
+        let mut bounds = None;
+        let half = self.half_width();
+        self.extend_bounds(self.root, -half, -half, &mut bounds);
+        bounds
+    }
+
+    fn level(&self) -> u32 {
+        self.nodes[self.root as usize].level as u32
+    }
+
+    fn half_width(&self) -> i64 {
+        1 << (self.level() - 1)
+    }
+
+    fn contains(&self, x: i64, y: i64) -> bool {
+        let half = self.half_width();
+        (-half..half).contains(&x) && (-half..half).contains(&y)
+    }
+
+    /// The node with these quadrants, created if it doesn't exist yet
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.index.get(&children) {
+            return id;
+        }
+        let id = self.nodes.len() as NodeId;
+        let population = children.iter().fold(0u64, |sum, &child| {
+            sum.saturating_add(self.nodes[child as usize].population)
+        });
+        self.nodes.push(Node {
+            level: self.nodes[children[0] as usize].level + 1,
+            children,
+            population,
+        });
+        self.index.insert(children, id);
+        id
+    }
+
+    fn empty_node(&mut self, level: u32) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let empty = *self.empty.last().unwrap();
+            let node = self.join([empty; 4]);
+            self.empty.push(node);
+        }
+        self.empty[level as usize]
+    }
+
+    fn children(&self, node: NodeId) -> [NodeId; 4] {
+        self.nodes[node as usize].children
+    }
+
+    /// Doubles the size of the universe, keeping the existing cells in the middle
+    fn expand(&mut self) {
+        let [nw, ne, sw, se] = self.children(self.root);
+        let empty = self.empty_node(self.level() - 1);
+        let nw = self.join([empty, empty, empty, nw]);
+        let ne = self.join([empty, empty, ne, empty]);
+        let sw = self.join([empty, sw, empty, empty]);
+        let se = self.join([se, empty, empty, empty]);
+        self.root = self.join([nw, ne, sw, se]);
+    }
+
+    /// Whether every live cell lies in the middle quarter of the root, far enough from the edges that the
+    /// pattern can't grow out of the center half during a step
+    fn is_padded(&self) -> bool {
+        let [nw, ne, sw, se] = self.children(self.root);
+        let inner = [
+            self.children(self.children(nw)[SE])[SE],
+            self.children(self.children(ne)[SW])[SW],
+            self.children(self.children(sw)[NE])[NE],
+            self.children(self.children(se)[NW])[NW],
+        ];
+        let inner_population = inner
+            .iter()
+            .map(|&node| self.nodes[node as usize].population)
+            .sum::<u64>();
+        inner_population == self.population()
+    }
+
+    /// Rebuilds `node` with the cell at `(x, y)`, relative to its bottom-left corner, set
+    fn set_in(&mut self, node: NodeId, x: i64, y: i64, is_alive: bool) -> NodeId {
+        let level = self.nodes[node as usize].level;
+        if level == 0 {
+            return if is_alive { ALIVE } else { DEAD };
+        }
+        let half = 1 << (level - 1);
+        let mut children = self.children(node);
+        let i = quadrant(x >= half, y >= half);
+        children[i] = self.set_in(children[i], x % half, y % half, is_alive);
+        self.join(children)
+    }
+
+    /// The middle half of a node, one level down, without advancing it
+    fn center(&mut self, node: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(node);
+        self.join([
+            self.children(nw)[SE],
+            self.children(ne)[SW],
+            self.children(sw)[NE],
+            self.children(se)[NW],
+        ])
+    }
+
+    /// The middle half of a node advanced by 2^min(`step_log2`, level - 2) generations, memoized
+    fn advance(&mut self, node: NodeId) -> NodeId {
+        if let Some(&result) = self.results.get(&node) {
+            return result;
+        }
+        let level = self.nodes[node as usize].level as u32;
+        let result = if self.nodes[node as usize].population == 0 {
+            self.empty_node(level - 1)
+        } else if level == 2 {
+            self.advance_base(node)
+        } else {
+            // Nine overlapping sub-squares of half the size, from the top-left to the bottom-right
+            let [nw, ne, sw, se] = self.children(node);
+            let [_, nw_ne, nw_sw, nw_se] = self.children(nw);
+            let [ne_nw, _, ne_sw, ne_se] = self.children(ne);
+            let [sw_nw, sw_ne, _, sw_se] = self.children(sw);
+            let [se_nw, se_ne, se_sw, _] = self.children(se);
+            let squares = [
+                nw,
+                self.join([nw_ne, ne_nw, nw_se, ne_sw]),
+                ne,
+                self.join([nw_sw, nw_se, sw_nw, sw_ne]),
+                self.join([nw_se, ne_sw, sw_ne, se_nw]),
+                self.join([ne_sw, ne_se, se_nw, se_ne]),
+                sw,
+                self.join([sw_ne, se_nw, sw_se, se_sw]),
+                se,
+            ];
+
+            // Advance twice at full speed, or take the centers first and only advance once when the step is
+            // smaller than this node can cover
+            let full_speed = self.step_log2 >= level - 2;
+            let mut r = [DEAD; 9];
+            for (r, square) in r.iter_mut().zip(squares) {
+                *r = if full_speed {
+                    self.advance(square)
+                } else {
+                    self.center(square)
+                };
+            }
+
+            let nw = self.join([r[0], r[1], r[3], r[4]]);
+            let ne = self.join([r[1], r[2], r[4], r[5]]);
+            let sw = self.join([r[3], r[4], r[6], r[7]]);
+            let se = self.join([r[4], r[5], r[7], r[8]]);
+            let quadrants = [nw, ne, sw, se].map(|quadrant| self.advance(quadrant));
+            self.join(quadrants)
+        };
+        self.results.insert(node, result);
+        result
+    }
+
+    /// Applies the rules once to the middle 2x2 cells of a 4x4 node
+    fn advance_base(&mut self, node: NodeId) -> NodeId {
+        let transitions = self.transitions.as_ref().expect("rules are set before stepping");
+        let cell = |x: i64, y: i64| {
+            let quadrant_node = self.children(node)[quadrant(x >= 2, y >= 2)];
+            self.children(quadrant_node)[quadrant(x % 2 == 1, y % 2 == 1)] == ALIVE
+        };
+
+        let next = |x: i64, y: i64| {
+            let mut block = 0;
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if cell(x + dx, y + dy) {
+                        block |= 1 << (3 * (dy + 1) + dx + 1);
+                    }
+                }
+            }
+            if transitions[block] {
+                ALIVE
+            } else {
+                DEAD
+            }
+        };
+        let children = [next(1, 2), next(2, 2), next(1, 1), next(2, 1)];
+        self.join(children)
+    }
+
+    /// Drops every node unreachable from the root, along with all memoized results
+    fn collect_garbage(&mut self) {
+        let mut fresh = HashLife::new();
+        let mut copied = HashMap::new();
+        fresh.root = fresh.copy_node(self, self.root, &mut copied);
+        self.nodes = fresh.nodes;
+        self.index = fresh.index;
+        self.empty = fresh.empty;
+        self.results.clear();
+        self.root = fresh.root;
+    }
+
+    fn copy_node(&mut self, from: &HashLife, node: NodeId, copied: &mut HashMap<NodeId, NodeId>) -> NodeId {
+        if node == DEAD || node == ALIVE {
+            return node;
+        }
+        if let Some(&id) = copied.get(&node) {
+            return id;
+        }
+        let children = from.children(node).map(|child| self.copy_node(from, child, copied));
+        let id = self.join(children);
+        copied.insert(node, id);
+        id
+    }
+
+    fn visit_live_blocks(&self, node: NodeId, x: i64, y: i64, window: &Window, f: &mut impl FnMut(usize, usize)) {
+        let Node {
+            level,
+            children,
+            population,
+        } = self.nodes[node as usize];
+        let size = 1 << level;
+        let overlaps =
+            x < window.x + window.width && x + size > window.x && y < window.y + window.height && y + size > window.y;
+        if population == 0 || !overlaps {
+            return;
+        }
+        if level as u32 <= window.scale_log2 {
+            f(
+                ((x - window.x) >> window.scale_log2) as usize,
+                ((y - window.y) >> window.scale_log2) as usize,
+            );
+            return;
+        }
+        let half = size / 2;
+        self.visit_live_blocks(children[NW], x, y + half, window, f);
+        self.visit_live_blocks(children[NE], x + half, y + half, window, f);
+        self.visit_live_blocks(children[SW], x, y, window, f);
+        self.visit_live_blocks(children[SE], x + half, y, window, f);
+    }
+
+    /// Grows `bounds` to cover the live cells of `node`, skipping nodes that lie entirely within them
+    fn extend_bounds(&self, node: NodeId, x: i64, y: i64, bounds: &mut Option<(i64, i64, i64, i64)>) {
+        let Node {
+            level,
+            children,
+            population,
+        } = self.nodes[node as usize];
+        if population == 0 {
+            return;
+        }
+        let size = 1 << level;
+        if let Some((min_x, min_y, max_x, max_y)) = *bounds {
+            if x >= min_x && y >= min_y && x + size - 1 <= max_x && y + size - 1 <= max_y {
+                return;
+            }
+        }
+        if level == 0 {
+            *bounds = Some(match *bounds {
+                Some((min_x, min_y, max_x, max_y)) => (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)),
+                None => (x, y, x, y),
+            });
+            return;
+        }
+        let half = size / 2;
+        self.extend_bounds(children[SW], x, y, bounds);
+        self.extend_bounds(children[SE], x + half, y, bounds);
+        self.extend_bounds(children[NW], x, y + half, bounds);
+        self.extend_bounds(children[NE], x + half, y + half, bounds);
+    }
+}
+
+/// The region of cells being drawn by `HashLife::for_each_live_block`
+struct Window {
+    x: i64,
+    y: i64,
+    width: i64,
+    height: i64,
+    scale_log2: u32,
+}
+
+/// Index into `Node::children` of the quadrant on the given sides
+fn quadrant(east: bool, north: bool) -> usize {
+    match (north, east) {
+        (true, false) => NW,
+        (true, true) => NE,
+        (false, false) => SW,
+        (false, true) => SE,
+    }
+}
+
+impl Board for HashLife {
+    fn place_cell(&mut self, x: isize, y: isize) -> bool {
+        self.set_alive(x as i64, y as i64, true);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::patterns::{Orientation, Pattern};
+    use crate::topology::Topology;
+
+    /// A bounded grid with an acorn in the middle, large enough that nothing reaches its edges for a while
+    fn acorn_grid() -> Grid {
+        let mut grid = Grid::new(256, 256);
+        grid.set_topology(Topology::Bounded);
+        Pattern::Acorn.add_to_grid(&mut grid, &[], Orientation::default(), 128, 128);
+        grid
+    }
+
+    /// Whether `alive` holds exactly the live cells of the grid, shifted so the grid's middle is at the origin
+    fn matches(grid: &Grid, alive: impl Fn(i64, i64) -> bool) -> bool {
+        (0..256).all(|y| (0..256).all(|x| alive(x as i64 - 128, y as i64 - 128) == grid.is_alive(x, y)))
+    }
+
+    #[test]
+    fn jumps_like_grid() {
+        for rulestring in ["B3/S23", "B36/S23", "B2-a/S12", "B2/S013V", "B2/S34H"] {
+            let rules: Rules = rulestring.parse().unwrap();
+            let mut grid = acorn_grid();
+            let mut hashlife = HashLife::new();
+            Pattern::Acorn.add_to_grid(&mut hashlife, &[], Orientation::default(), 0, 0);
+            let mut generation = 0;
+            // Repeating a jump size reuses the cached results, and changing it throws them away
+            for step_log2 in [0, 3, 3, 1, 4, 0, 2] {
+                hashlife.step(&rules, step_log2);
+                for _ in 0..1 << step_log2 {
+                    grid.step(&rules);
+                }
+                generation += 1 << step_log2;
+                assert_eq!(hashlife.population(), grid.population() as u64);
+                assert!(
+                    matches(&grid, |x, y| hashlife.is_alive(x, y)),
+                    "{rulestring}, generation {generation}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use crate::config;
+use crate::state::GameState;
+use game_of_life_bevy::export;
+use game_of_life_bevy::grid::Grid;
+use game_of_life_bevy::rules::SelectedRules;
+use game_of_life_bevy::universe::Universe;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::path::Path;
+use std::time::Instant;
+
+/// Runs the simulation without a window and prints a summary of the final grid.
+///
+/// The grid starts with every loaded pattern stamped at its center, switching to the rules each declares with
+/// `--pattern-rules`, or a random grid if none were given. With the hashlife or infinite engine the same cells are
+/// placed in the unbounded universe instead, and HashLife covers the generations in power-of-two jumps.
+pub fn run(mut game_state: GameState, generations: u64, seed: Option<u64>, output: Option<&Path>) {
+    if game_state.loaded_patterns.is_empty() {
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let (width, height) = (game_state.grid.width(), game_state.grid.height());
+        let topology = game_state.grid.topology();
+        game_state.grid = Grid::random(width, height, 0.2, &mut rng); // 20% of cells alive
+        game_state.grid.set_topology(topology);
+        if let Some(universe) = &mut game_state.universe {
+            for y in 0..height {
+                for x in 0..width {
+                    if game_state.grid.is_alive(x, y) {
+                        universe.set_alive(x as i64, y as i64, true);
+                    }
+                }
+            }
+        }
+    } else {
+        for index in 0..game_state.loaded_patterns.len() {
+            // Like placing the pattern in the window, optionally switching to the rules it declares
+            if let Some(rules) = game_state.loaded_patterns[index].rules.clone() {
+                if game_state.use_pattern_rules {
+                    if let Err(err) = game_state.set_rules(SelectedRules::from(rules)) {
+                        eprintln!("warning: kept the current rules: {}", err);
+                    }
+                }
+            }
+            let pattern = &game_state.loaded_patterns[index];
+            let width = pattern.cells.iter().map(|&(x, _)| x + 1).max().unwrap_or(0);
+            let height = pattern.cells.iter().map(|&(_, y)| y + 1).max().unwrap_or(0);
+            let x = (game_state.grid.width().saturating_sub(width) / 2) as isize;
+            let y = (game_state.grid.height().saturating_sub(height) / 2) as isize;
+            if let Some(universe) = &mut game_state.universe {
+                // The universe is unbounded, so nothing is clipped
+                pattern.add_to_grid(universe, x, y);
+            } else if pattern.add_to_grid(&mut game_state.grid, x, y) {
+                eprintln!("warning: {} was clipped at the edge of the grid", pattern.name);
+            }
+        }
+    }
+
+    let rules = game_state.selected_rules.to_rules();
+    let start = Instant::now();
+    let population = match &mut game_state.universe {
+        Some(Universe::HashLife(hashlife)) => {
+            // Repeat the largest jump, which keeps HashLife's memoized results between steps, then cover the rest
+            // with each smaller power of two it is made of
+            let largest = generations.checked_ilog2().unwrap_or(0).min(config::MAX_STEP_EXPONENT);
+            for _ in 0..generations >> largest {
+                hashlife.step(&rules, largest);
+            }
+            for step_exponent in (0..largest).rev() {
+                if generations & 1 << step_exponent != 0 {
+                    hashlife.step(&rules, step_exponent);
+                }
+            }
+            hashlife.population()
+        }
+        Some(Universe::Sparse(sparse)) => {
+            for _ in 0..generations {
+                sparse.step(&rules);
+            }
+            sparse.population()
+        }
+        None => {
+            for _ in 0..generations {
+                game_state.grid.step(&rules);
+            }
+            game_state.grid.population() as u64
+        }
+    };
+    let elapsed = start.elapsed();
+
+    println!("rules: {}", game_state.selected_rules);
+    println!("generations: {}", generations);
+    println!("population: {}", population);
+    println!(
+        "elapsed: {:.3?} ({:.1} generations/s)",
+        elapsed,
+        generations as f64 / elapsed.as_secs_f64()
+    );
+
+    if let Some(path) = output {
+        let grid = match &game_state.universe {
+            Some(universe) => universe.to_grid().unwrap_or_else(|| {
+                eprintln!("error: the live cells are spread too far apart to save");
+                std::process::exit(1);
+            }),
+            None => game_state.grid,
+        };
+        if let Err(err) = export::write_file(path, &grid, &rules) {
+            eprintln!("error: could not save grid to {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+        println!("saved: {}", path.display());
+    }
+}
@@ -0,0 +1,110 @@
+use crate::grid::Grid;
+use std::collections::VecDeque;
+
+/// A snapshot of the grid's packed cell words and dying-age planes, copied as they are stored
+#[derive(Clone)]
+pub struct Frame {
+    cells: Vec<u64>,
+    /// Age bit planes of dying cells under Generations rules; empty for two-state rules
+    dying: Vec<u64>,
+    planes: usize,
+    /// Whether the cells were stored inverted to emulate a B0 rule
+    inverted: bool,
+    /// Whether the next Margolus step would have started its blocks at odd cells
+    odd_blocks: bool,
+}
+
+impl Frame {
+    pub fn capture(grid: &Grid) -> Self {
+        let (cells, dying, planes) = grid.words();
+        Self {
+            cells: cells.to_vec(),
+            dying: dying.to_vec(),
+            planes,
+            inverted: grid.is_inverted(),
+            odd_blocks: grid.odd_blocks(),
+        }
+    }
+
+    /// Writes the snapshot back into a grid of the same size
+    pub fn restore(&self, grid: &mut Grid) {
+        grid.set_words(&self.cells, &self.dying, self.planes);
+        grid.set_inverted(self.inverted);
+        grid.set_odd_blocks(self.odd_blocks);
+    }
+
+    /// Memory held by the snapshot's words
+    fn bytes(&self) -> usize {
+        (self.cells.len() + self.dying.len()) * std::mem::size_of::<u64>()
+    }
+}
+
+/// Undo/redo timeline of grid states, covering both generation steps and edits, bounded by the memory its
+/// frames take up so large grids keep fewer of them
+pub struct History {
+    past: VecDeque<Frame>,
+    future: Vec<Frame>,
+    /// Bytes held by the frames in `past` and `future`
+    bytes: usize,
+    max_bytes: usize,
+}
+
+impl History {
+    /// Creates a history that forgets the oldest frames once they take up more than `max_bytes`
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            past: VecDeque::new(),
+            future: Vec::new(),
+            bytes: 0,
+            max_bytes,
+        }
+    }
+
+    /// Saves the grid before it changes; any undone frames are discarded
+    pub fn record(&mut self, grid: &Grid) {
+        self.bytes -= self.future.iter().map(Frame::bytes).sum::<usize>();
+        self.future.clear();
+        self.push_past(Frame::capture(grid));
+    }
+
+    /// Restores the previous state, returning `false` if there is none
+    pub fn undo(&mut self, grid: &mut Grid) -> bool {
+        let Some(frame) = self.past.pop_back() else {
+            return false;
+        };
+        let current = Frame::capture(grid);
+        self.bytes += current.bytes();
+        self.bytes -= frame.bytes();
+        self.future.push(current);
+        frame.restore(grid);
+        true
+    }
+
+    /// Restores the state that was last undone, returning `false` if there is none
+    pub fn redo(&mut self, grid: &mut Grid) -> bool {
+        let Some(frame) = self.future.pop() else {
+            return false;
+        };
+        self.bytes -= frame.bytes();
+        self.push_past(Frame::capture(grid));
+        frame.restore(grid);
+        true
+    }
+
+    /// Forgets every frame, e.g. after the grid is resized
+    pub fn clear(&mut self) {
+        self.past.clear();
+        self.future.clear();
+        self.bytes = 0;
+    }
+
+    /// Saves a frame, forgetting the oldest ones while over the memory limit but always keeping the newest
+    fn push_past(&mut self, frame: Frame) {
+        self.bytes += frame.bytes();
+        self.past.push_back(frame);
+        while self.bytes > self.max_bytes && self.past.len() > 1 {
+            let oldest = self.past.pop_front().expect("past holds more than one frame");
+            self.bytes -= oldest.bytes();
+        }
+    }
+}
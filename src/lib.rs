@@ -0,0 +1,13 @@
+//! Bevy-independent Game of Life simulation core: grids, rules, patterns and pattern files.
+//!
+//! The `game_of_life_bevy` binary is a Bevy front end over this library.
+pub mod export;
+pub mod grid;
+pub mod hashlife;
+pub mod history;
+pub mod patterns;
+pub mod rle;
+pub mod rules;
+pub mod sparse;
+pub mod topology;
+pub mod universe;
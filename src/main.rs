@@ -2,74 +2,139 @@ use bevy::prelude::*;
 use clap::Parser;
 mod args;
 mod config;
-mod patterns;
-mod rules;
+mod headless;
 mod state;
 mod systems;
 
 use bevy_embedded_assets::EmbeddedAssetPlugin;
-use rand::Rng;
+use game_of_life_bevy::grid::Grid;
+use game_of_life_bevy::hashlife::HashLife;
+use game_of_life_bevy::history::History;
+use game_of_life_bevy::rules::Neighborhood;
+use game_of_life_bevy::sparse::SparseUniverse;
+use game_of_life_bevy::topology::Topology;
+use game_of_life_bevy::universe::Universe;
+use game_of_life_bevy::{patterns, rle};
 
 fn main() {
     let args = args::Args::parse();
     let mode = config::Mode::from(args.mode);
-    let tick_duration = if args.speed != 1.0 { 1.0 / args.speed } else { 1.0 };
+    let ticks_per_second = args
+        .speed
+        .clamp(config::MIN_TICKS_PER_SECOND, config::MAX_TICKS_PER_SECOND);
+    let loaded_patterns: Vec<_> = args
+        .patterns
+        .iter()
+        .map(|path| {
+            rle::read_file(path).unwrap_or_else(|err| {
+                eprintln!("error: {}", err);
+                std::process::exit(1);
+            })
+        })
+        .collect();
+    if args.topology == Topology::Sphere && args.width != args.height {
+        eprintln!("error: the sphere topology needs a square grid (--width equal to --height)");
+        std::process::exit(1);
+    }
+    let universe = match args.engine {
+        args::Engine::Grid => None,
+        args::Engine::HashLife => Some(Universe::HashLife(HashLife::new())),
+        args::Engine::Infinite => Some(Universe::Sparse(SparseUniverse::new())),
+    };
+    if universe.is_some() && !Universe::supports(&args.rules.to_rules()) {
+        eprintln!("error: the hashlife and infinite engines only run two-state square 3x3 rules without B0");
+        std::process::exit(1);
+    }
+    let mut grid = Grid::new(args.width, args.height);
+    grid.set_topology(args.topology);
     let game_state = state::GameState {
-        cells: generate_empty_grid(),
-        next_cells: vec![vec![false; config::GRID_WIDTH]; config::GRID_HEIGHT],
+        grid,
+        follow_population: universe.is_some(),
+        universe,
+        step_exponent: args.step_exponent,
+        cell_size: args.cell_size,
         mode,
-        selected_pattern: patterns::Pattern::Glider,
+        hexagonal: args.rules.to_rules().neighborhood == Neighborhood::Hexagonal,
+        // Start with the first loaded pattern, if any were given
+        selected_pattern: if loaded_patterns.is_empty() {
+            patterns::Pattern::Glider
+        } else {
+            patterns::Pattern::Loaded(0)
+        },
+        pattern_orientation: patterns::Orientation::default(),
+        brush: None,
         selected_rules: args.rules,
+        paused: args.paused,
+        ticks_per_second,
+        fast_forward: false,
+        generation: 0,
+        history: History::new(config::HISTORY_MEMORY),
+        loaded_patterns,
+        use_pattern_rules: args.pattern_rules,
+        export_path: args.export.clone().unwrap_or_else(|| "export.rle".into()),
     };
+    if args.headless {
+        headless::run(game_state, args.generations, args.seed, args.export.as_deref());
+        return;
+    }
     App::new()
         .add_plugins((EmbeddedAssetPlugin::default(), DefaultPlugins))
         .insert_resource(game_state)
+        .init_resource::<state::RuleEntry>()
+        .init_resource::<state::TickCounter>()
         .add_systems(Startup, systems::setup)
         .add_systems(
             FixedUpdate, // lets args.speed control fixed update rate
-            (systems::update_cells, systems::render_cells, systems::render_images),
+            systems::update_cells.run_if(systems::simulation_running.and_then(not(systems::fast_forward_enabled))),
         )
         .add_systems(
             Update,
             (
+                systems::update_layout.run_if(not(systems::universe_enabled)),
+                // Redrawn at most once a frame, and only when the grid or anything else about the game changed,
+                // after the layout and size changes that respawn the cells
+                (systems::render_cells, systems::render_hex_cells, systems::render_images)
+                    .run_if(not(systems::universe_enabled).and_then(resource_changed::<state::GameState>))
+                    .after(systems::update_layout)
+                    .after(systems::resize_grid),
                 systems::trigger_selected_pattern,
-                systems::change_selected_pattern,
-                systems::change_selected_rules,
+                systems::paint_cells,
+                systems::update_pattern_preview,
+                systems::zoom_camera,
+                systems::drag_camera,
+                systems::follow_population.run_if(systems::follow_population_enabled),
+                systems::render_universe
+                    .run_if(systems::universe_enabled)
+                    .after(systems::follow_population),
+                systems::fast_forward.run_if(systems::simulation_running.and_then(systems::fast_forward_enabled)),
+                // Keyboard shortcuts are ignored while a rulestring is being typed
+                (
+                    systems::change_selected_pattern,
+                    systems::select_brush,
+                    systems::orient_selected_pattern,
+                    systems::change_selected_rules,
+                    systems::kill_all_cells,
+                    systems::export_grid,
+                    systems::resize_grid.run_if(not(systems::universe_enabled)),
+                    systems::toggle_paused,
+                    systems::step_once,
+                    systems::change_speed,
+                    systems::change_step_exponent.run_if(systems::hashlife_enabled),
+                    systems::undo_redo.run_if(not(systems::universe_enabled)),
+                    systems::move_camera,
+                    systems::toggle_follow_population.run_if(systems::universe_enabled),
+                )
+                    .run_if(systems::rule_entry_inactive)
+                    .before(systems::edit_rule_entry),
+                systems::edit_rule_entry,
+                systems::load_dropped_patterns,
                 systems::update_selected_pattern_text,
                 systems::update_selected_rules_text,
-                systems::kill_all_cells,
+                systems::update_rule_entry_text,
+                systems::update_paused_text,
+                systems::update_speed_text,
             ),
         )
-        .insert_resource(Time::<Fixed>::from_seconds(tick_duration))
+        .insert_resource(Time::<Fixed>::from_hz(ticks_per_second))
         .run();
 }
-
-// Generate a random initial grid state
-#[allow(dead_code)]
-fn generate_random_grid() -> Vec<Vec<state::Cell>> {
-    let mut rng = rand::thread_rng();
-    (0..config::GRID_HEIGHT)
-        .map(|_| {
-            (0..config::GRID_WIDTH)
-                .map(|_| state::Cell {
-                    is_alive: rng.gen_bool(0.2), // 20% chance of cell being alive
-                    activation_count: 0,
-                })
-                .collect()
-        })
-        .collect()
-}
-
-#[allow(dead_code)]
-fn generate_empty_grid() -> Vec<Vec<state::Cell>> {
-    (0..config::GRID_HEIGHT)
-        .map(|_| {
-            (0..config::GRID_WIDTH)
-                .map(|_| state::Cell {
-                    is_alive: false,
-                    activation_count: 0,
-                })
-                .collect()
-        })
-        .collect()
-}
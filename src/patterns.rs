@@ -1,7 +1,8 @@
-use crate::state::Cell;
+use crate::rules::{RuleSet, Rules, CONDUCTOR, ELECTRON_HEAD, ELECTRON_TAIL};
+use std::sync::OnceLock;
 
 #[allow(dead_code)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Pattern {
     Single,
     Glider,
@@ -22,34 +23,161 @@ pub enum Pattern {
     GosperGliderGun,
     Diehard,
     Acorn,
+    /// Wireworld circuit that lets electrons through from the left but not from the right
+    Diode,
+    /// Wireworld loop that sends an electron down its wire every 12 generations
+    ElectronClock,
+    /// Wireworld gate passing on an electron from either or both of its inputs
+    OrGate,
+    /// A pattern loaded from a file, indexing into `GameState::loaded_patterns`
+    Loaded(usize),
+}
+
+/// A pattern described by its live cells rather than hardcoded, e.g. one read from an RLE file
+#[derive(Clone, Debug)]
+pub struct LoadedPattern {
+    pub name: String,
+    /// Offsets of live cells, or of every cell in any state for multi-state files, with `(0, 0)` at the
+    /// bottom-left of the bounding box
+    pub cells: Vec<(usize, usize)>,
+    /// State of each of `cells`, for files written with the multi-state letters such as Generations or Wireworld
+    /// patterns
+    pub states: Option<Vec<u8>>,
+    /// Rules declared by the file, if any
+    pub rules: Option<Rules>,
+}
+
+impl LoadedPattern {
+    /// Stamps the pattern like `Pattern::add_to_grid`, returning `true` if any cells were clipped
+    pub fn add_to_grid(&self, board: &mut impl Board, x: isize, y: isize) -> bool {
+        place_cells(board, &self.cells, self.states.as_deref(), x, y)
+    }
+}
+
+/// Anything patterns can be stamped into: the grid, or one of the unbounded universes
+pub trait Board {
+    /// Makes the cell at a position that may lie off the board alive, mapping it back through the board's
+    /// topology if it has edges. Returns `false` if the position fell off the edge of a bounded grid.
+    fn place_cell(&mut self, x: isize, y: isize) -> bool;
+
+    /// Sets the cell at a position that may lie off the board to `state`, like `place_cell`. Boards that only
+    /// hold live and dead cells just place the live ones, in state 1.
+    fn place_state(&mut self, x: isize, y: isize, state: u8) -> bool {
+        state != 1 || self.place_cell(x, y)
+    }
+}
+
+/// Quarter turns and mirroring applied to a pattern before it is placed
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Orientation {
+    /// Number of 90° clockwise turns, 0-3
+    pub quarter_turns: u8,
+    /// Mirrored left to right before turning
+    pub mirrored: bool,
+}
+
+impl Orientation {
+    pub fn rotated(self) -> Self {
+        Self {
+            quarter_turns: (self.quarter_turns + 1) % 4,
+            ..self
+        }
+    }
+
+    pub fn flipped(self) -> Self {
+        Self {
+            mirrored: !self.mirrored,
+            ..self
+        }
+    }
+
+    /// Applies the orientation to cell offsets, shifting the result so its bottom-left corner is back at `(0, 0)`
+    pub fn apply(self, cells: &[(usize, usize)]) -> Vec<(usize, usize)> {
+        let oriented: Vec<(isize, isize)> = cells
+            .iter()
+            .map(|&(x, y)| {
+                let (mut x, mut y) = (x as isize, y as isize);
+                if self.mirrored {
+                    x = -x;
+                }
+                for _ in 0..self.quarter_turns {
+                    (x, y) = (y, -x); // Clockwise, with y pointing up
+                }
+                (x, y)
+            })
+            .collect();
+
+        let min_x = oriented.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let min_y = oriented.iter().map(|&(_, y)| y).min().unwrap_or(0);
+        oriented
+            .into_iter()
+            .map(|(x, y)| ((x - min_x) as usize, (y - min_y) as usize))
+            .collect()
+    }
+}
+
+/// The single placement path for every pattern: each cell goes through `Board::place_cell`, or
+/// `Board::place_state` when the pattern has `states` for its cells, so on a grid it wraps or is clipped the same
+/// way as neighbor counting treats the edges
+fn place_cells(board: &mut impl Board, cells: &[(usize, usize)], states: Option<&[u8]>, x: isize, y: isize) -> bool {
+    let mut clipped = false;
+    for (i, &(dx, dy)) in cells.iter().enumerate() {
+        let (x, y) = (x + dx as isize, y + dy as isize);
+        clipped |= !match states {
+            Some(states) => board.place_state(x, y, states[i]),
+            None => board.place_cell(x, y),
+        };
+    }
+    clipped
 }
 
 impl Pattern {
-    pub fn add_to_grid(&self, cells: &mut [Vec<Cell>], x: usize, y: usize) {
+    /// Stamps the pattern with its bottom-left corner at `(x, y)`, mapping cells that land off a grid through
+    /// the grid's topology. Returns `true` if any cells fell off a bounded edge and were clipped.
+    pub fn add_to_grid(
+        &self,
+        board: &mut impl Board,
+        loaded_patterns: &[LoadedPattern],
+        orientation: Orientation,
+        x: isize,
+        y: isize,
+    ) -> bool {
+        // Orienting keeps the cells in order, so they still line up with their states
+        let states = self.states(loaded_patterns);
+        place_cells(board, &orientation.apply(self.cells(loaded_patterns)), states, x, y)
+    }
+
+    /// Offsets of the pattern's live cells, or of every cell of a circuit, from its bottom-left corner
+    pub fn cells<'a>(&self, loaded_patterns: &'a [LoadedPattern]) -> &'a [(usize, usize)] {
         match self {
-            Pattern::Single => add_single(cells, x, y),
-            Pattern::Glider => add_glider(cells, x, y),
-            Pattern::Blinker => add_blinker(cells, x, y),
-            Pattern::Toad => add_toad(cells, x, y),
-            Pattern::Beacon => add_beacon(cells, x, y),
-            Pattern::Pulsar => add_pulsar(cells, x, y),
-            Pattern::Block => add_block(cells, x, y),
-            Pattern::LightweightSpaceship => add_lightweight_spaceship(cells, x, y),
-            Pattern::MiddleweightSpaceship => add_middleweight_spaceship(cells, x, y),
-            Pattern::HeavyweightSpaceship => add_heavyweight_spaceship(cells, x, y),
-            Pattern::PentaDecathlon => add_penta_decathlon(cells, x, y),
-            Pattern::Clock => add_clock(cells, x, y),
-            Pattern::Beehive => add_beehive(cells, x, y),
-            Pattern::Loaf => add_loaf(cells, x, y),
-            Pattern::Boat => add_boat(cells, x, y),
-            Pattern::Tub => add_tub(cells, x, y),
-            Pattern::GosperGliderGun => add_gosper_glider_gun(cells, x, y),
-            Pattern::Diehard => add_diehard(cells, x, y),
-            Pattern::Acorn => add_acorn(cells, x, y),
+            Pattern::Single => SINGLE,
+            Pattern::Glider => GLIDER,
+            Pattern::Blinker => BLINKER,
+            Pattern::Toad => TOAD,
+            Pattern::Beacon => BEACON,
+            Pattern::Pulsar => PULSAR,
+            Pattern::Block => BLOCK,
+            Pattern::LightweightSpaceship => LIGHTWEIGHT_SPACESHIP,
+            Pattern::MiddleweightSpaceship => MIDDLEWEIGHT_SPACESHIP,
+            Pattern::HeavyweightSpaceship => HEAVYWEIGHT_SPACESHIP,
+            Pattern::PentaDecathlon => PENTA_DECATHLON,
+            Pattern::Clock => CLOCK,
+            Pattern::Beehive => BEEHIVE,
+            Pattern::Loaf => LOAF,
+            Pattern::Boat => BOAT,
+            Pattern::Tub => TUB,
+            Pattern::GosperGliderGun => GOSPER_GLIDER_GUN,
+            Pattern::Diehard => DIEHARD,
+            Pattern::Acorn => ACORN,
+            Pattern::Diode | Pattern::ElectronClock | Pattern::OrGate => {
+                &self.circuit().expect("circuit patterns have a drawing").cells
+            }
+            Pattern::Loaded(index) => &loaded_patterns[*index].cells,
         }
     }
 
-    pub fn next(&self) -> Pattern {
+    /// Cycles forward through the built-in patterns, followed by the `loaded` file patterns
+    pub fn next(&self, loaded: usize) -> Pattern {
         use Pattern::*;
         match self {
             Single => Glider,
@@ -70,14 +198,46 @@ impl Pattern {
             Tub => GosperGliderGun,
             GosperGliderGun => Diehard,
             Diehard => Acorn,
-            Acorn => Single, // Wrap around to the first pattern
+            Acorn => Diode,
+            Diode => ElectronClock,
+            ElectronClock => OrGate,
+            OrGate if loaded > 0 => Loaded(0),
+            OrGate => Single, // Wrap around to the first pattern
+            Loaded(index) if index + 1 < loaded => Loaded(index + 1),
+            Loaded(_) => Single,
         }
     }
 
-    pub fn previous(&self) -> Pattern {
+    /// State of each cell from `cells`, for patterns that hold more than live cells
+    fn states<'a>(&self, loaded_patterns: &'a [LoadedPattern]) -> Option<&'a [u8]> {
+        match self {
+            Pattern::Loaded(index) => loaded_patterns[*index].states.as_deref(),
+            pattern => pattern.circuit().map(|circuit| circuit.states.as_slice()),
+        }
+    }
+
+    /// Rules a built-in pattern only works under, which placing it switches to
+    pub fn rules(&self) -> Option<Rules> {
+        self.circuit().map(|_| RuleSet::Wireworld.to_rules())
+    }
+
+    /// The cells and states of a Wireworld circuit pattern, parsed from its drawing on first use
+    fn circuit(&self) -> Option<&'static Circuit> {
+        static CIRCUITS: OnceLock<[Circuit; 3]> = OnceLock::new();
+        let circuits = CIRCUITS.get_or_init(|| [DIODE, ELECTRON_CLOCK, OR_GATE].map(Circuit::parse));
+        match self {
+            Pattern::Diode => Some(&circuits[0]),
+            Pattern::ElectronClock => Some(&circuits[1]),
+            Pattern::OrGate => Some(&circuits[2]),
+            _ => None,
+        }
+    }
+
+    pub fn previous(&self, loaded: usize) -> Pattern {
         use Pattern::*;
         match self {
-            Single => Acorn, // Wrap around to the last pattern
+            Single if loaded > 0 => Loaded(loaded - 1),
+            Single => OrGate, // Wrap around to the last pattern
             Glider => Single,
             Blinker => Glider,
             Toad => Blinker,
@@ -96,255 +256,200 @@ impl Pattern {
             GosperGliderGun => Tub,
             Diehard => GosperGliderGun,
             Acorn => Diehard,
+            Diode => Acorn,
+            ElectronClock => Diode,
+            OrGate => ElectronClock,
+            Loaded(0) => OrGate,
+            Loaded(index) => Loaded(index - 1),
         }
     }
 }
 
-fn add_single(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    cells[y][x].is_alive = true;
-}
+const SINGLE: &[(usize, usize)] = &[(0, 0)];
 
-fn add_glider(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    // Coordinates for a glider pattern
-    let glider_coords = [(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)];
-    for (dx, dy) in glider_coords {
-        cells[y + dy][x + dx].is_alive = true;
-    }
-}
+// Coordinates for a glider pattern
+const GLIDER: &[(usize, usize)] = &[(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)];
 
-fn add_blinker(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    // Coordinates for a blinker pattern
-    let blinker_coords = [(0, 1), (1, 1), (2, 1)];
-    for (dx, dy) in blinker_coords {
-        cells[y + dy][x + dx].is_alive = true;
-    }
-}
+// Coordinates for a blinker pattern
+const BLINKER: &[(usize, usize)] = &[(0, 1), (1, 1), (2, 1)];
 
-fn add_toad(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    let toad_coords = [(1, 0), (2, 0), (3, 0), (0, 1), (1, 1), (2, 1)];
-    for (dx, dy) in toad_coords {
-        cells[y + dy][x + dx].is_alive = true;
-    }
-}
+const TOAD: &[(usize, usize)] = &[(1, 0), (2, 0), (3, 0), (0, 1), (1, 1), (2, 1)];
 
-fn add_beacon(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    let beacon_coords = [(0, 0), (1, 0), (0, 1), (1, 1), (2, 2), (3, 2), (2, 3), (3, 3)];
-    for (dx, dy) in beacon_coords {
-        cells[y + dy][x + dx].is_alive = true;
-    }
-}
+const BEACON: &[(usize, usize)] = &[(0, 0), (1, 0), (0, 1), (1, 1), (2, 2), (3, 2), (2, 3), (3, 3)];
 
-fn add_pulsar(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    let pulsar_coords = [
-        (2, 0),
-        (3, 0),
-        (4, 0),
-        (8, 0),
-        (9, 0),
-        (10, 0),
-        (0, 2),
-        (5, 2),
-        (7, 2),
-        (12, 2),
-        (0, 3),
-        (5, 3),
-        (7, 3),
-        (12, 3),
-        (0, 4),
-        (5, 4),
-        (7, 4),
-        (12, 4),
-        (2, 5),
-        (3, 5),
-        (4, 5),
-        (8, 5),
-        (9, 5),
-        (10, 5),
-        (2, 7),
-        (3, 7),
-        (4, 7),
-        (8, 7),
-        (9, 7),
-        (10, 7),
-        (0, 8),
-        (5, 8),
-        (7, 8),
-        (12, 8),
-        (0, 9),
-        (5, 9),
-        (7, 9),
-        (12, 9),
-        (0, 10),
-        (5, 10),
-        (7, 10),
-        (12, 10),
-        (2, 12),
-        (3, 12),
-        (4, 12),
-        (8, 12),
-        (9, 12),
-        (10, 12),
-    ];
-    for (dx, dy) in pulsar_coords {
-        cells[y + dy][x + dx].is_alive = true;
-    }
-}
+const PULSAR: &[(usize, usize)] = &[
+    (2, 0),
+    (3, 0),
+    (4, 0),
+    (8, 0),
+    (9, 0),
+    (10, 0),
+    (0, 2),
+    (5, 2),
+    (7, 2),
+    (12, 2),
+    (0, 3),
+    (5, 3),
+    (7, 3),
+    (12, 3),
+    (0, 4),
+    (5, 4),
+    (7, 4),
+    (12, 4),
+    (2, 5),
+    (3, 5),
+    (4, 5),
+    (8, 5),
+    (9, 5),
+    (10, 5),
+    (2, 7),
+    (3, 7),
+    (4, 7),
+    (8, 7),
+    (9, 7),
+    (10, 7),
+    (0, 8),
+    (5, 8),
+    (7, 8),
+    (12, 8),
+    (0, 9),
+    (5, 9),
+    (7, 9),
+    (12, 9),
+    (0, 10),
+    (5, 10),
+    (7, 10),
+    (12, 10),
+    (2, 12),
+    (3, 12),
+    (4, 12),
+    (8, 12),
+    (9, 12),
+    (10, 12),
+];
 
-fn add_block(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    let block_coords = [(0, 0), (1, 0), (0, 1), (1, 1)];
-    for (dx, dy) in block_coords {
-        cells[y + dy][x + dx].is_alive = true;
-    }
-}
+const BLOCK: &[(usize, usize)] = &[(0, 0), (1, 0), (0, 1), (1, 1)];
 
-fn add_lightweight_spaceship(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    let lwss_coords = [(0, 1), (3, 1), (4, 2), (0, 3), (4, 3), (1, 4), (2, 4), (3, 4)];
-    for (dx, dy) in lwss_coords {
-        cells[y + dy][x + dx].is_alive = true;
-    }
-}
+const LIGHTWEIGHT_SPACESHIP: &[(usize, usize)] = &[(0, 1), (3, 1), (4, 2), (0, 3), (4, 3), (1, 4), (2, 4), (3, 4)];
 
-fn add_middleweight_spaceship(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    let mwss_coords = [(0, 1), (4, 1), (5, 2), (0, 3), (5, 3), (1, 4), (2, 4), (3, 4), (4, 4)];
-    for (dx, dy) in mwss_coords {
-        cells[y + dy][x + dx].is_alive = true;
-    }
-}
+const MIDDLEWEIGHT_SPACESHIP: &[(usize, usize)] =
+    &[(0, 1), (4, 1), (5, 2), (0, 3), (5, 3), (1, 4), (2, 4), (3, 4), (4, 4)];
 
-fn add_heavyweight_spaceship(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    let hwss_coords = [
-        (0, 1),
-        (5, 1),
-        (6, 2),
-        (0, 3),
-        (6, 3),
-        (1, 4),
-        (2, 4),
-        (3, 4),
-        (4, 4),
-        (5, 4),
-    ];
-    for (dx, dy) in hwss_coords {
-        cells[y + dy][x + dx].is_alive = true;
-    }
-}
+const HEAVYWEIGHT_SPACESHIP: &[(usize, usize)] = &[
+    (0, 1),
+    (5, 1),
+    (6, 2),
+    (0, 3),
+    (6, 3),
+    (1, 4),
+    (2, 4),
+    (3, 4),
+    (4, 4),
+    (5, 4),
+];
 
-fn add_penta_decathlon(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    let penta_decathlon_coords = [
-        (1, 0),
-        (1, 1),
-        (1, 2),
-        (1, 3),
-        (1, 5),
-        (1, 6),
-        (1, 7),
-        (1, 8),
-        (0, 4),
-        (2, 4),
-    ];
-    for (dx, dy) in penta_decathlon_coords {
-        cells[y + dy][x + dx].is_alive = true;
-    }
-}
+const PENTA_DECATHLON: &[(usize, usize)] = &[
+    (1, 0),
+    (1, 1),
+    (1, 2),
+    (1, 3),
+    (1, 5),
+    (1, 6),
+    (1, 7),
+    (1, 8),
+    (0, 4),
+    (2, 4),
+];
 
-fn add_clock(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    let clock_coords = [(1, 0), (2, 0), (0, 1), (3, 1), (0, 2), (3, 2), (1, 3), (2, 3)];
-    for (dx, dy) in clock_coords {
-        cells[y + dy][x + dx].is_alive = true;
-    }
-}
+const CLOCK: &[(usize, usize)] = &[(1, 0), (2, 0), (0, 1), (3, 1), (0, 2), (3, 2), (1, 3), (2, 3)];
 
-fn add_beehive(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    let beehive_coords = [(1, 0), (2, 0), (0, 1), (3, 1), (1, 2), (2, 2)];
-    for (dx, dy) in beehive_coords {
-        cells[y + dy][x + dx].is_alive = true;
-    }
-}
+const BEEHIVE: &[(usize, usize)] = &[(1, 0), (2, 0), (0, 1), (3, 1), (1, 2), (2, 2)];
 
-fn add_loaf(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    let loaf_coords = [(1, 0), (2, 0), (0, 1), (3, 1), (1, 2), (3, 2), (2, 3)];
-    for (dx, dy) in loaf_coords {
-        cells[y + dy][x + dx].is_alive = true;
-    }
-}
+const LOAF: &[(usize, usize)] = &[(1, 0), (2, 0), (0, 1), (3, 1), (1, 2), (3, 2), (2, 3)];
 
-fn add_boat(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    let boat_coords = [(0, 0), (1, 0), (2, 1), (0, 1), (1, 2)];
-    for (dx, dy) in boat_coords {
-        cells[y + dy][x + dx].is_alive = true;
-    }
-}
+const BOAT: &[(usize, usize)] = &[(0, 0), (1, 0), (2, 1), (0, 1), (1, 2)];
 
-fn add_tub(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    let tub_coords = [(1, 0), (0, 1), (2, 1), (1, 2)];
-    for (dx, dy) in tub_coords {
-        cells[y + dy][x + dx].is_alive = true;
-    }
+const TUB: &[(usize, usize)] = &[(1, 0), (0, 1), (2, 1), (1, 2)];
+
+const GOSPER_GLIDER_GUN: &[(usize, usize)] = &[
+    (0, 4),
+    (1, 4),
+    (0, 5),
+    (1, 5),
+    (10, 4),
+    (10, 5),
+    (10, 6),
+    (11, 3),
+    (11, 7),
+    (12, 2),
+    (12, 8),
+    (13, 2),
+    (13, 8),
+    (14, 5),
+    (15, 3),
+    (15, 7),
+    (16, 4),
+    (16, 5),
+    (16, 6),
+    (17, 5),
+    (20, 2),
+    (20, 3),
+    (20, 4),
+    (21, 2),
+    (21, 3),
+    (21, 4),
+    (22, 1),
+    (22, 5),
+    (24, 0),
+    (24, 1),
+    (24, 5),
+    (24, 6),
+    (34, 2),
+    (34, 3),
+    (35, 2),
+    (35, 3),
+];
+
+const DIEHARD: &[(usize, usize)] = &[(0, 1), (1, 1), (1, 2), (5, 2), (6, 2), (7, 2), (6, 0)];
+
+const ACORN: &[(usize, usize)] = &[(1, 0), (3, 1), (0, 2), (1, 2), (4, 2), (5, 2), (6, 2)];
+
+/// Cells of a Wireworld circuit and the state of each, in the same order
+struct Circuit {
+    cells: Vec<(usize, usize)>,
+    states: Vec<u8>,
 }
 
-fn add_gosper_glider_gun(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    let gosper_glider_gun_coords = [
-        (0, 4),
-        (1, 4),
-        (0, 5),
-        (1, 5),
-        (10, 4),
-        (10, 5),
-        (10, 6),
-        (11, 3),
-        (11, 7),
-        (12, 2),
-        (12, 8),
-        (13, 2),
-        (13, 8),
-        (14, 5),
-        (15, 3),
-        (15, 7),
-        (16, 4),
-        (16, 5),
-        (16, 6),
-        (17, 5),
-        (20, 2),
-        (20, 3),
-        (20, 4),
-        (21, 2),
-        (21, 3),
-        (21, 4),
-        (22, 1),
-        (22, 5),
-        (24, 0),
-        (24, 1),
-        (24, 5),
-        (24, 6),
-        (34, 2),
-        (34, 3),
-        (35, 2),
-        (35, 3),
-    ];
-
-    let max_x = cells[0].len();
-    let max_y = cells.len();
-
-    for (dx, dy) in gosper_glider_gun_coords {
-        let new_x = x + dx;
-        let new_y = y + dy;
-
-        if new_x < max_x && new_y < max_y {
-            cells[new_y][new_x].is_alive = true;
+impl Circuit {
+    /// Reads a drawing whose rows run top to bottom: `#` is conductor, `H` an electron head, `t` an electron tail
+    /// and a space is empty
+    fn parse(drawing: &[&str]) -> Self {
+        let mut circuit = Circuit {
+            cells: Vec::new(),
+            states: Vec::new(),
+        };
+        for (row, line) in drawing.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let state = match c {
+                    '#' => CONDUCTOR,
+                    'H' => ELECTRON_HEAD,
+                    't' => ELECTRON_TAIL,
+                    _ => continue,
+                };
+                circuit.cells.push((x, drawing.len() - 1 - row));
+                circuit.states.push(state);
+            }
         }
+        circuit
     }
 }
 
-fn add_diehard(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    let diehard_coords = [(0, 1), (1, 1), (1, 2), (5, 2), (6, 2), (7, 2), (6, 0)];
-    for (dx, dy) in diehard_coords {
-        cells[y + dy][x + dx].is_alive = true;
-    }
-}
+// The electron on the left passes the gap; one coming from the right would excite both of the blobs around it
+// and stop there
+const DIODE: &[&str] = &["       ##", "tH###### ######", "       ##"];
 
-fn add_acorn(cells: &mut [Vec<Cell>], x: usize, y: usize) {
-    let acorn_coords = [(1, 0), (3, 1), (0, 2), (1, 2), (4, 2), (5, 2), (6, 2)];
-    for (dx, dy) in acorn_coords {
-        cells[y + dy][x + dx].is_alive = true;
-    }
-}
+const ELECTRON_CLOCK: &[&str] = &[" ###tH", "#     ##########", " #####"];
+
+// An electron from either input excites all three cells across the junction, which then keep it from running
+// back up the other input
+const OR_GATE: &[&str] = &["tH##", "    #", "   #######", "    #", "####"];
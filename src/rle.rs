@@ -0,0 +1,190 @@
+use crate::patterns::LoadedPattern;
+use crate::rules::Rules;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RleError(String);
+
+impl fmt::Display for RleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for RleError {}
+
+/// Reads a Run Length Encoded (`.rle`) pattern file, named after the file unless it has a `#N` line
+pub fn read_file(path: &Path) -> Result<LoadedPattern, RleError> {
+    let text =
+        std::fs::read_to_string(path).map_err(|err| RleError(format!("could not read {}: {}", path.display(), err)))?;
+    let mut pattern = parse(&text)?;
+    if pattern.name.is_empty() {
+        pattern.name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+    }
+    Ok(pattern)
+}
+
+/// Parses RLE text: `#` comment lines, an `x = .., y = .., rule = ..` header, then `b`/`o`/`$` runs ending in `!`
+pub fn parse(text: &str) -> Result<LoadedPattern, RleError> {
+    let mut name = String::new();
+    let mut header = None;
+    let mut body = String::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(comment) = line.strip_prefix('#') {
+            if let Some(pattern_name) = comment.strip_prefix('N') {
+                name = pattern_name.trim().to_string();
+            }
+        } else if header.is_none() && line.starts_with('x') {
+            header = Some(parse_header(line)?);
+        } else {
+            body.push_str(line);
+        }
+    }
+
+    let (height, rules) = header.ok_or_else(|| RleError("missing 'x = .., y = ..' header line".into()))?;
+
+    // Multi-state files keep the state of every cell, such as the dying cells of Generations rules or the wires of
+    // Wireworld; otherwise every letter is a live cell
+    let multi_state = rules.as_ref().is_some_and(|rules: &Rules| rules.states > 2);
+
+    // Decode runs top to bottom as written, flipping rows afterwards so (0, 0) is the bottom-left
+    let mut rows: Vec<Vec<(usize, u8)>> = vec![Vec::new()];
+    let mut x = 0;
+    let mut run = String::new();
+    // Run length and letter of a `p`..`y` prefix, which multi-state files put before the letter of states above 24
+    let mut prefixed_run = None;
+    for c in body.chars() {
+        if c.is_ascii_digit() {
+            run.push(c);
+            continue;
+        }
+        let count = if let Some((count, _)) = prefixed_run {
+            count
+        } else if run.is_empty() {
+            1
+        } else {
+            run.parse::<usize>()
+                .map_err(|_| RleError(format!("run length '{}' is too large", run)))?
+        };
+        run.clear();
+        if ('p'..='y').contains(&c) && prefixed_run.is_none() {
+            prefixed_run = Some((count, c));
+            continue;
+        }
+        let prefix = prefixed_run.take().map(|(_, prefix)| prefix);
+
+        match c {
+            '!' => break,
+            '$' => {
+                rows.extend((0..count).map(|_| Vec::new()));
+                x = 0;
+            }
+            // Dead cells: `b` for two-state patterns, `.` for multi-state ones
+            'b' | '.' => x += count,
+            // States `A`..`X` are 1-24, and each `p`..`y` prefix adds another 24
+            'A'..='X' if multi_state => {
+                let prefix_states = prefix.map_or(0, |prefix| (prefix as u32 - 'p' as u32 + 1) * 24);
+                let state = u8::try_from(prefix_states + c as u32 - 'A' as u32 + 1).map_err(|_| {
+                    RleError(format!(
+                        "state {}{} is above the last state, 255",
+                        prefix.unwrap_or_default(),
+                        c
+                    ))
+                })?;
+                rows.last_mut().unwrap().extend((x..x + count).map(|x| (x, state)));
+                x += count;
+            }
+            // Anything else is a live cell (`o`, or `A`..`X` in files without multi-state rules)
+            c if c.is_ascii_alphabetic() => {
+                rows.last_mut().unwrap().extend((x..x + count).map(|x| (x, 1)));
+                x += count;
+            }
+            c => return Err(RleError(format!("unexpected character '{}' in pattern data", c))),
+        }
+    }
+
+    let height = height.max(rows.len());
+    let (cells, states): (Vec<_>, Vec<_>) = rows
+        .iter()
+        .enumerate()
+        .flat_map(|(row, xs)| xs.iter().map(move |&(x, state)| ((x, height - 1 - row), state)))
+        .unzip();
+
+    Ok(LoadedPattern {
+        name,
+        cells,
+        states: multi_state.then_some(states),
+        rules,
+    })
+}
+
+/// Parses `x = 3, y = 3, rule = B3/S23` into its height and optional rules
+fn parse_header(line: &str) -> Result<(usize, Option<Rules>), RleError> {
+    let mut width: Option<usize> = None;
+    let mut height = None;
+    let mut rulestring: Option<String> = None;
+    for field in line.split(',') {
+        // Fields without `=` continue the previous value, like the `,100` in `rule = B3/S23:T100,100` or the
+        // commas of a Larger than Life rule
+        let Some((key, value)) = field.split_once('=') else {
+            if let Some(rulestring) = &mut rulestring {
+                rulestring.push(',');
+                rulestring.push_str(field.trim());
+            }
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "x" => width = value.parse().ok(),
+            "y" => height = value.parse().ok(),
+            "rule" => rulestring = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    // Golly appends the bounded grid size after a colon, e.g. `B3/S23:T100,100`
+    let rules = rulestring
+        .map(|rulestring| rulestring.split(':').next().unwrap_or_default().parse())
+        .transpose()
+        .map_err(|err| RleError(format!("invalid rule: {}", err)))?;
+
+    match (width, height) {
+        (Some(_), Some(height)) => Ok((height, rules)),
+        _ => Err(RleError(format!("header '{}' needs numeric x and y values", line))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::to_rle;
+    use crate::grid::Grid;
+    use crate::patterns::Board;
+
+    /// Every state of a Wireworld circuit survives being exported and read back in
+    #[test]
+    fn wireworld_round_trip() {
+        let rules: Rules = "WireWorld".parse().unwrap();
+        let mut grid = Grid::new(10, 10);
+        for (x, y, state) in [(6, 7, 1), (3, 3, 3), (2, 2, 2), (4, 2, 1), (0, 0, 1)] {
+            grid.place_state(x, y, state);
+        }
+        let rle = to_rle(&grid, &rules);
+
+        let pattern = parse(&rle).unwrap();
+        assert_eq!(pattern.rules, Some(rules));
+        assert_eq!(pattern.cells.len(), 5);
+        let mut loaded = Grid::new(10, 10);
+        pattern.add_to_grid(&mut loaded, 0, 0);
+        for y in 0..10 {
+            for x in 0..10 {
+                assert_eq!(loaded.state(x, y), grid.state(x, y), "cell ({x}, {y}) of {rle}");
+            }
+        }
+    }
+}
@@ -1,10 +1,154 @@
 use clap::ValueEnum;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
 
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Rules {
     pub survival_counts: Vec<usize>,
     pub birth_counts: Vec<usize>,
+    pub neighborhood: Neighborhood,
+    /// Number of cell states, written `C` in Generations rulestrings (e.g. `B2/S/C3`). Cells that don't survive
+    /// pass through `states - 2` dying states before they are dead, and only dead cells can be born; plain
+    /// Life-like rules have 2 states.
+    pub states: usize,
+    /// How far the neighborhood reaches: 1 for the 3x3 block, more for Larger than Life rules written like
+    /// `R5,C0,M1,S34..58,B34..45,NM`
+    pub range: usize,
+    /// Whether a cell counts itself among its neighbors, `M1` in Larger than Life rulestrings
+    pub include_center: bool,
+    /// Which arrangements of each neighbor count match, for isotropic non-totalistic rules such as `B2-a/S12`;
+    /// `None` when every arrangement of the listed counts does
+    pub isotropic: Option<Isotropic>,
+    /// Block rule of a Margolus automaton, written `MS,D` and 16 entries separated by `;` as in MCell (e.g.
+    /// `MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15`). Entry `b` is what a 2x2 block holding `b` turns into, with
+    /// bits 1, 2, 4 and 8 for its top-left, top-right, bottom-left and bottom-right cells. The grid is split into
+    /// blocks whose corners move one cell diagonally every generation; the counts are unused.
+    pub margolus: Option<[u8; 16]>,
+    /// Runs Wireworld, written `WireWorld` as in Golly, with 4 states: 1 is an electron head, 2 an electron tail and
+    /// 3 conductor. Heads become tails and tails conductor, and conductor becomes a head next to one or two heads.
+    /// The counts are unused.
+    pub wireworld: bool,
 }
 
+/// The Hensel letters of an isotropic non-totalistic rule. For each neighbor count from 0 to 8, bit `i` is set
+/// when the count's `i`th letter in `HENSEL_LETTERS` order matches; a count with every bit set is written as a
+/// plain digit.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Isotropic {
+    pub birth: [u16; 9],
+    pub survival: [u16; 9],
+}
+
+/// Which surrounding cells count as neighbors
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Neighborhood {
+    /// The 8 surrounding cells
+    Moore,
+    /// The 4 orthogonally adjacent cells, written with a `V` suffix (e.g. `B2/S013V`)
+    VonNeumann,
+    /// The 6 cells around a hexagonal cell, written with an `H` suffix (e.g. `B2/S34H`). The grid is sheared into
+    /// hexagons, so these are the Moore neighbors except the top-left and bottom-right ones.
+    Hexagonal,
+}
+
+/// Wireworld states, numbered as in Golly
+pub const ELECTRON_HEAD: u8 = 1;
+pub const ELECTRON_TAIL: u8 = 2;
+pub const CONDUCTOR: u8 = 3;
+
+/// Most states a Generations rule can have, as in Golly
+pub const MAX_STATES: usize = 256;
+
+/// Largest Larger than Life range, as in Golly
+pub const MAX_RANGE: usize = 500;
+
+/// Hensel's letters for the distinct arrangements of 0 to 4 Moore neighbors, up to rotation and reflection.
+/// Counts 5 to 8 reuse the letters of 3 to 0, each naming the complement of that arrangement.
+const HENSEL_LETTERS: [&str; 5] = ["", "ce", "cekain", "cekainyqjr", "cekainyqjrtwz"];
+
+// Bits of a 3x3 block in `Rules::transition_table` indices, from the bottom-left cell, with y pointing up
+const SW: u16 = 1 << 0;
+const S: u16 = 1 << 1;
+const SE: u16 = 1 << 2;
+const W: u16 = 1 << 3;
+const CENTER: u16 = 1 << 4;
+const E: u16 = 1 << 5;
+const NW: u16 = 1 << 6;
+const N: u16 = 1 << 7;
+const NE: u16 = 1 << 8;
+
+/// One arrangement for each of `HENSEL_LETTERS`; the rest of a letter's arrangements are its rotations and
+/// reflections
+const HENSEL_ARRANGEMENTS: [&[u16]; 5] = [
+    &[0],
+    &[NW, N],
+    &[
+        NW | NE, // c
+        N | W,   // e
+        N | SE,  // k
+        N | NE,  // a
+        N | S,   // i
+        NE | SW, // n
+    ],
+    &[
+        NW | NE | SW, // c
+        N | E | W,    // e
+        N | E | SW,   // k
+        N | NE | E,   // a
+        NW | N | NE,  // i
+        NW | NE | W,  // n
+        NW | NE | S,  // y
+        N | NE | SW,  // q
+        N | E | NW,   // j
+        N | S | NE,   // r
+    ],
+    &[
+        NW | NE | SW | SE, // c
+        N | E | S | W,     // e
+        N | NE | SE | W,   // k
+        N | NE | E | SE,   // a
+        N | NE | S | SE,   // i
+        N | NE | SE | NW,  // n
+        N | NE | SE | SW,  // y
+        N | NE | E | SW,   // q
+        N | NE | S | W,    // j
+        N | NE | E | S,    // r
+        NW | N | NE | S,   // t
+        N | NE | SW | W,   // w
+        NW | N | S | SE,   // z
+    ],
+];
+
+impl Neighborhood {
+    /// Number of cells around the center within `range`
+    pub fn max_neighbors(self, range: usize) -> usize {
+        match self {
+            Neighborhood::Moore => (2 * range + 1) * (2 * range + 1) - 1,
+            Neighborhood::VonNeumann => 2 * range * (range + 1),
+            Neighborhood::Hexagonal => 3 * range * (range + 1),
+        }
+    }
+}
+
+/// The rules currently driving the simulation: a named preset or a rulestring entered by the user
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SelectedRules {
+    Preset(RuleSet),
+    Custom(Rules),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseRulesError(String);
+
+impl fmt::Display for ParseRulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseRulesError {}
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
 pub enum RuleSet {
     Conway,
@@ -20,6 +164,19 @@ pub enum RuleSet {
     Replicator,
     Fredkin,
     Stains,
+    BriansBrain,
+    StarWars,
+    Frogs,
+    Sticks,
+    Bosco,
+    Majority,
+    Waffle,
+    Globe,
+    Critters,
+    Tron,
+    BilliardBallMachine,
+    SingleRotation,
+    Wireworld,
 }
 
 impl RuleSet {
@@ -38,6 +195,19 @@ impl RuleSet {
             RuleSet::Replicator => Rules::replicator(),
             RuleSet::Fredkin => Rules::fredkin(),
             RuleSet::Stains => Rules::stains(),
+            RuleSet::BriansBrain => Rules::brians_brain(),
+            RuleSet::StarWars => Rules::star_wars(),
+            RuleSet::Frogs => Rules::frogs(),
+            RuleSet::Sticks => Rules::sticks(),
+            RuleSet::Bosco => Rules::bosco(),
+            RuleSet::Majority => Rules::majority(),
+            RuleSet::Waffle => Rules::waffle(),
+            RuleSet::Globe => Rules::globe(),
+            RuleSet::Critters => Rules::critters(),
+            RuleSet::Tron => Rules::tron(),
+            RuleSet::BilliardBallMachine => Rules::billiard_ball_machine(),
+            RuleSet::SingleRotation => Rules::single_rotation(),
+            RuleSet::Wireworld => Rules::wireworld(),
         }
     }
 
@@ -56,14 +226,27 @@ impl RuleSet {
             Morley => Replicator,
             Replicator => Fredkin,
             Fredkin => Stains,
-            Stains => Conway, // Wrap around to the first pattern
+            Stains => BriansBrain,
+            BriansBrain => StarWars,
+            StarWars => Frogs,
+            Frogs => Sticks,
+            Sticks => Bosco,
+            Bosco => Majority,
+            Majority => Waffle,
+            Waffle => Globe,
+            Globe => Critters,
+            Critters => Tron,
+            Tron => BilliardBallMachine,
+            BilliardBallMachine => SingleRotation,
+            SingleRotation => Wireworld,
+            Wireworld => Conway, // Wrap around to the first pattern
         }
     }
 
     pub fn previous(&self) -> RuleSet {
         use RuleSet::*;
         match self {
-            Conway => Stains, // Wrap around to the last pattern
+            Conway => Wireworld, // Wrap around to the last pattern
             Highlife => Conway,
             DayAndNight => Highlife,
             Seeds => DayAndNight,
@@ -76,16 +259,588 @@ impl RuleSet {
             Replicator => Morley,
             Fredkin => Replicator,
             Stains => Fredkin,
+            BriansBrain => Stains,
+            StarWars => BriansBrain,
+            Frogs => StarWars,
+            Sticks => Frogs,
+            Bosco => Sticks,
+            Majority => Bosco,
+            Waffle => Majority,
+            Globe => Waffle,
+            Critters => Globe,
+            Tron => Critters,
+            BilliardBallMachine => Tron,
+            SingleRotation => BilliardBallMachine,
+            Wireworld => SingleRotation,
+        }
+    }
+}
+
+impl SelectedRules {
+    pub fn to_rules(&self) -> Rules {
+        match self {
+            SelectedRules::Preset(rule_set) => rule_set.to_rules(),
+            SelectedRules::Custom(rules) => rules.clone(),
+        }
+    }
+
+    pub fn next(&self) -> SelectedRules {
+        match self {
+            SelectedRules::Preset(rule_set) => SelectedRules::Preset(rule_set.next()),
+            SelectedRules::Custom(_) => SelectedRules::Preset(RuleSet::Conway), // Back to the first preset
         }
     }
+
+    pub fn previous(&self) -> SelectedRules {
+        match self {
+            SelectedRules::Preset(rule_set) => SelectedRules::Preset(rule_set.previous()),
+            SelectedRules::Custom(_) => SelectedRules::Preset(RuleSet::Wireworld), // Back to the last preset
+        }
+    }
+}
+
+impl From<Rules> for SelectedRules {
+    /// Prefers a preset when the rules match one, so its name is shown instead of the rulestring
+    fn from(rules: Rules) -> Self {
+        RuleSet::value_variants()
+            .iter()
+            .find(|rule_set| rule_set.to_rules() == rules)
+            .map_or(SelectedRules::Custom(rules), |&rule_set| {
+                SelectedRules::Preset(rule_set)
+            })
+    }
+}
+
+impl fmt::Display for SelectedRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectedRules::Preset(rule_set) => write!(f, "{:?}", rule_set),
+            SelectedRules::Custom(rules) => write!(f, "{}", rules),
+        }
+    }
+}
+
+impl FromStr for SelectedRules {
+    type Err = ParseRulesError;
+
+    /// Accepts either a preset name (e.g. `day-and-night`) or a rulestring (e.g. `B36/S23`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match RuleSet::from_str(s.trim(), true) {
+            Ok(rule_set) => Ok(SelectedRules::Preset(rule_set)),
+            Err(_) => s.parse().map(SelectedRules::Custom),
+        }
+    }
+}
+
+impl fmt::Display for Rules {
+    /// Formats as a canonical `B3/S23` rulestring, with Hensel letters for isotropic non-totalistic rules, a `/C`
+    /// state count for Generations rules and a `V` or `H` suffix for the von Neumann or hexagonal neighborhood, in
+    /// Larger than Life notation when the neighborhood needs it, in MCell notation for Margolus rules, or as
+    /// `WireWorld`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.wireworld {
+            return f.write_str("WireWorld");
+        }
+        if let Some(table) = self.margolus {
+            let entries: Vec<String> = table.iter().map(u8::to_string).collect();
+            return write!(f, "MS,D{}", entries.join(";"));
+        }
+        if self.is_larger_than_life() {
+            return self.fmt_larger_than_life(f);
+        }
+        f.write_str("B")?;
+        write_counts(f, &self.birth_counts, self.isotropic.map(|isotropic| isotropic.birth))?;
+        f.write_str("/S")?;
+        write_counts(
+            f,
+            &self.survival_counts,
+            self.isotropic.map(|isotropic| isotropic.survival),
+        )?;
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+        match self.neighborhood {
+            Neighborhood::Moore => Ok(()),
+            Neighborhood::VonNeumann => f.write_str("V"),
+            Neighborhood::Hexagonal => f.write_str("H"),
+        }
+    }
+}
+
+impl FromStr for Rules {
+    type Err = ParseRulesError;
+
+    /// Parses Life-like rulestrings in either `B3/S23` or `23/3` (survival/birth) notation, with Hensel letters
+    /// after any count for isotropic non-totalistic rules (`B2-a/S12`), optionally followed by a Generations state
+    /// count (`B2/S/C3` or `/2/3`) and then `V` for the von Neumann neighborhood or `H` for
+    /// the hexagonal one. Rulestrings starting with `R` are read as Larger than Life and ones starting with `MS,D`
+    /// as Margolus block rules, and `WireWorld` as Wireworld.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.starts_with(['R', 'r']) {
+            return parse_larger_than_life(s);
+        }
+        if s.starts_with(['M', 'm']) {
+            return parse_margolus(s);
+        }
+        if s.eq_ignore_ascii_case("wireworld") {
+            return Ok(Rules::wireworld());
+        }
+        let (body, neighborhood) = if let Some(body) = s.strip_suffix(['V', 'v']) {
+            (body, Neighborhood::VonNeumann)
+        } else if let Some(body) = s.strip_suffix(['H', 'h']) {
+            (body, Neighborhood::Hexagonal)
+        } else {
+            (s, Neighborhood::Moore)
+        };
+
+        let (first, rest) = body
+            .split_once('/')
+            .ok_or_else(|| ParseRulesError(format!("rulestring '{}' must contain a '/'", s)))?;
+        let (second, states) = match rest.split_once('/') {
+            Some((second, states)) => (second, parse_states(states)?),
+            None => (rest, 2),
+        };
+
+        let (birth, survival) = match (first.chars().next(), second.chars().next()) {
+            (Some('B' | 'b'), Some('S' | 's')) => (&first[1..], &second[1..]),
+            (Some('S' | 's'), Some('B' | 'b')) => (&second[1..], &first[1..]),
+            _ if !first.starts_with(char::is_alphabetic) && !second.starts_with(char::is_alphabetic) => {
+                (second, first) // S/B notation lists survival first
+            }
+            _ => {
+                return Err(ParseRulesError(format!(
+                    "rulestring '{}' must be in B.../S... or S/B notation",
+                    s
+                )))
+            }
+        };
+
+        let (survival_counts, survival_letters) = parse_counts(survival, neighborhood)?;
+        let (birth_counts, birth_letters) = parse_counts(birth, neighborhood)?;
+        let is_totalistic = [(&birth_counts, birth_letters), (&survival_counts, survival_letters)]
+            .iter()
+            .all(|(counts, letters)| counts.iter().all(|&count| letters[count] == all_letters(count)));
+        Ok(Rules {
+            survival_counts,
+            birth_counts,
+            neighborhood,
+            states,
+            range: 1,
+            include_center: false,
+            isotropic: (!is_totalistic).then_some(Isotropic {
+                birth: birth_letters,
+                survival: survival_letters,
+            }),
+            margolus: None,
+            wireworld: false,
+        })
+    }
+}
+
+/// Writes each count as a digit, followed by its Hensel letters when only some of its arrangements match: the
+/// matching letters, or a minus and the rest if that is shorter
+fn write_counts(f: &mut fmt::Formatter<'_>, counts: &[usize], letters: Option<[u16; 9]>) -> fmt::Result {
+    for &count in counts {
+        write!(f, "{}", count)?;
+        let Some(letters) = letters.filter(|letters| letters[count] != all_letters(count)) else {
+            continue;
+        };
+        let named = |mask: u16| -> String {
+            HENSEL_LETTERS[count.min(8 - count)]
+                .chars()
+                .enumerate()
+                .filter(|(i, _)| mask & 1 << i != 0)
+                .map(|(_, letter)| letter)
+                .collect()
+        };
+        let (kept, left_out) = (named(letters[count]), named(all_letters(count) & !letters[count]));
+        if kept.len() <= left_out.len() {
+            f.write_str(&kept)?;
+        } else {
+            write!(f, "-{}", left_out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parses an MCell Margolus rulestring such as `MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15`: `MS,D` followed by
+/// the 16 entries of the block rule, separated by `;`. Entry `b` is the block that a block holding `b` turns into.
+fn parse_margolus(s: &str) -> Result<Rules, ParseRulesError> {
+    let entries = s
+        .get(..4)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("MS,D"))
+        .map(|_| &s[4..])
+        .ok_or_else(|| ParseRulesError(format!("Margolus rulestring '{}' must start with 'MS,D'", s)))?;
+    let table: Vec<u8> = entries
+        .split(';')
+        .map(|entry| match entry.trim().parse() {
+            Ok(block) if block < 16 => Ok(block),
+            _ => Err(ParseRulesError(format!(
+                "Margolus block '{}' is not a number from 0 to 15",
+                entry
+            ))),
+        })
+        .collect::<Result<_, _>>()?;
+    let table = table
+        .try_into()
+        .map_err(|table: Vec<u8>| ParseRulesError(format!("Margolus rules need 16 blocks, not {}", table.len())))?;
+    Ok(Rules::margolus(table))
+}
+
+/// Parses a Larger than Life rulestring such as `R5,C0,M1,S34..58,B34..45,NM`: the range, state count (0 or 2
+/// for two states), whether the center counts, survival and birth intervals, and `NM` (Moore), `NN` (von
+/// Neumann) or `NH` (hexagonal). `S` and `B` may be repeated or left out, and a single count needs no `..`.
+fn parse_larger_than_life(s: &str) -> Result<Rules, ParseRulesError> {
+    let invalid = |item: &str| ParseRulesError(format!("unexpected '{}' in Larger than Life rulestring", item));
+    let mut range = None;
+    let mut states = 2;
+    let mut include_center = false;
+    let mut neighborhood = Neighborhood::Moore;
+    let mut intervals = Vec::new();
+
+    for item in s.split(',').map(str::trim) {
+        let (tag, value) = item.split_at(item.chars().next().map_or(0, char::len_utf8));
+        match tag.to_ascii_uppercase().as_str() {
+            "R" => match value.parse() {
+                Ok(r) if (1..=MAX_RANGE).contains(&r) => range = Some(r),
+                _ => {
+                    return Err(ParseRulesError(format!(
+                        "range '{}' must be a number from 1 to {}",
+                        value, MAX_RANGE
+                    )))
+                }
+            },
+            "C" => states = parse_states(value).or_else(|err| if value == "0" { Ok(2) } else { Err(err) })?,
+            "M" => {
+                include_center = match value {
+                    "0" => false,
+                    "1" => true,
+                    _ => return Err(invalid(item)),
+                }
+            }
+            "S" | "B" => intervals.push((tag.eq_ignore_ascii_case("B"), value)),
+            "N" => {
+                neighborhood = match value {
+                    "M" | "m" => Neighborhood::Moore,
+                    "N" | "n" => Neighborhood::VonNeumann,
+                    "H" | "h" => Neighborhood::Hexagonal,
+                    _ => return Err(invalid(item)),
+                }
+            }
+            _ => return Err(invalid(item)),
+        }
+    }
+    let range = range.ok_or_else(|| ParseRulesError(format!("rulestring '{}' is missing its range", s)))?;
+
+    // Counts can only be checked against the neighborhood size once the whole rulestring is read
+    let max_count = neighborhood.max_neighbors(range) + include_center as usize;
+    let (mut birth_counts, mut survival_counts) = (Vec::new(), Vec::new());
+    for (is_birth, interval) in intervals {
+        let (low, high) = interval.split_once("..").unwrap_or((interval, interval));
+        let (Ok(low), Ok(high)) = (low.parse::<usize>(), high.parse::<usize>()) else {
+            return Err(ParseRulesError(format!("interval '{}' must be like 34..58", interval)));
+        };
+        if low > high || high > max_count {
+            return Err(ParseRulesError(format!(
+                "interval '{}' must run upwards within 0-{}",
+                interval, max_count
+            )));
+        }
+        let counts = if is_birth {
+            &mut birth_counts
+        } else {
+            &mut survival_counts
+        };
+        counts.extend(low..=high);
+    }
+    for counts in [&mut birth_counts, &mut survival_counts] {
+        counts.sort_unstable();
+        counts.dedup();
+    }
+
+    Ok(Rules {
+        survival_counts,
+        birth_counts,
+        neighborhood,
+        states,
+        range,
+        include_center,
+        isotropic: None,
+        margolus: None,
+        wireworld: false,
+    })
+}
+
+/// Parses the state count of a Generations rulestring, with or without its `C` prefix
+fn parse_states(s: &str) -> Result<usize, ParseRulesError> {
+    let digits = s.strip_prefix(['C', 'c']).unwrap_or(s);
+    match digits.parse() {
+        Ok(states) if (2..=MAX_STATES).contains(&states) => Ok(states),
+        _ => Err(ParseRulesError(format!(
+            "state count '{}' must be a number from 2 to {}",
+            s, MAX_STATES
+        ))),
+    }
+}
+
+/// Parses a run of neighbor-count digits, each optionally followed by Hensel letters (`2ak`) or by a minus and
+/// the letters to leave out (`2-a`), into a sorted, de-duplicated list of counts and the letters of each
+fn parse_counts(digits: &str, neighborhood: Neighborhood) -> Result<(Vec<usize>, [u16; 9]), ParseRulesError> {
+    let mut letters = [0; 9];
+    let mut chars = digits.chars().peekable();
+    while let Some(c) = chars.next() {
+        let count = c
+            .to_digit(10)
+            .ok_or_else(|| ParseRulesError(format!("unexpected character '{}' in rulestring", c)))?
+            as usize;
+        if count > neighborhood.max_neighbors(1) {
+            return Err(ParseRulesError(format!(
+                "neighbor count {} is outside 0-{}",
+                count,
+                neighborhood.max_neighbors(1)
+            )));
+        }
+        let is_negated = chars.next_if_eq(&'-').is_some();
+        let mut named = 0;
+        while let Some(letter) = chars.next_if(char::is_ascii_alphabetic) {
+            if neighborhood != Neighborhood::Moore {
+                return Err(ParseRulesError(format!(
+                    "Hensel letters such as '{}' need the Moore neighborhood",
+                    letter
+                )));
+            }
+            let index = HENSEL_LETTERS[count.min(8 - count)]
+                .find(letter.to_ascii_lowercase())
+                .ok_or_else(|| {
+                    ParseRulesError(format!("'{}' is not a Hensel letter for {} neighbors", letter, count))
+                })?;
+            named |= 1 << index;
+        }
+        letters[count] |= match (is_negated, named) {
+            (false, 0) => all_letters(count),
+            (false, named) => named,
+            (true, 0) => {
+                return Err(ParseRulesError(format!(
+                    "'-' after {} must be followed by the Hensel letters to leave out",
+                    count
+                )))
+            }
+            (true, named) => all_letters(count) & !named,
+        };
+    }
+    let counts = (0..letters.len()).filter(|&count| letters[count] != 0).collect();
+    Ok((counts, letters))
+}
+
+/// Letter bits covering every arrangement of `count` neighbors; counts 0 and 8 have a single unnamed one
+fn all_letters(count: usize) -> u16 {
+    let letters = HENSEL_LETTERS[count.min(8 - count)].len().max(1);
+    (1 << letters) - 1
+}
+
+/// Index into `HENSEL_LETTERS` of the letter naming an arrangement of Moore neighbors, given as block bits
+fn hensel_letter(arrangement: u16) -> usize {
+    static LETTERS: OnceLock<[u8; 512]> = OnceLock::new();
+    let letters = LETTERS.get_or_init(|| {
+        std::array::from_fn(|block| {
+            let arrangement = block as u16 & !CENTER;
+            let count = arrangement.count_ones() as usize;
+            // Counts above 4 are named after their complement
+            let named = if count > 4 {
+                arrangement ^ (0x1ff & !CENTER)
+            } else {
+                arrangement
+            };
+            HENSEL_ARRANGEMENTS[count.min(8 - count)]
+                .iter()
+                .position(|&representative| symmetries(representative).any(|image| image == named))
+                .expect("every arrangement is a rotation or reflection of one with a letter") as u8
+        })
+    });
+    letters[arrangement as usize] as usize
+}
+
+/// Letter bits naming the complements of the arrangements of `count` neighbors in `letters`. Counts above 4 are
+/// named after their complements, so only the arrangements of 4 can change letter.
+fn complement_letters(count: usize, letters: u16) -> u16 {
+    if count != 4 {
+        return letters;
+    }
+    HENSEL_ARRANGEMENTS[4]
+        .iter()
+        .enumerate()
+        .filter(|&(letter, _)| letters & 1 << letter != 0)
+        .fold(0, |complement, (_, &arrangement)| {
+            complement | 1 << hensel_letter(arrangement ^ (0x1ff & !CENTER))
+        })
+}
+
+/// The eight rotations and reflections of a 3x3 block
+fn symmetries(block: u16) -> impl Iterator<Item = u16> {
+    (0..8).map(move |symmetry| {
+        (0..9).filter(|bit| block & 1 << bit != 0).fold(0, |image, bit: i32| {
+            let (mut dx, mut dy) = (bit % 3 - 1, bit / 3 - 1);
+            if symmetry >= 4 {
+                dx = -dx;
+            }
+            for _ in 0..symmetry % 4 {
+                (dx, dy) = (dy, -dx);
+            }
+            image | 1 << (3 * (dy + 1) + dx + 1)
+        })
+    })
 }
 
 impl Rules {
+    /// Whether the rules need more than the 3x3 neighborhood around each cell, or count the cell itself
+    pub fn is_larger_than_life(&self) -> bool {
+        self.range > 1 || self.include_center
+    }
+
+    /// Most live cells a cell can count: every neighbor, plus itself under `M1`
+    fn max_count(&self) -> usize {
+        self.neighborhood.max_neighbors(self.range) + self.include_center as usize
+    }
+
+    /// Emulates B0 rules the standard way, without bringing the whole empty background to life every other
+    /// generation: each generation whose background would be alive is stored inverted, so the stored background
+    /// stays dead. Given whether the current generation is stored inverted, returns rules without B0 that step it
+    /// to the next generation as it is stored, and whether that one is inverted.
+    ///
+    /// Rules without B0 step uninverted generations unchanged. Generations rules are never inverted, since dying
+    /// cells have no opposite state, so an inverted generation has to be complemented back before they step it.
+    /// Margolus rules that fill empty blocks, such as Critters, are emulated the same way.
+    pub fn b0_emulation(&self, inverted: bool) -> (Rules, bool) {
+        if let Some(table) = self.margolus {
+            let flip = if inverted { 15 } else { 0 };
+            let next_inverted = table[flip] == 15;
+            let next_flip = if next_inverted { 15 } else { 0 };
+            let table = std::array::from_fn(|block| table[block ^ flip] ^ next_flip);
+            return (Rules::margolus(table), next_inverted);
+        }
+        let max_count = self.max_count();
+        let next_inverted = self.states == 2
+            && if inverted {
+                self.survival_counts.contains(&max_count)
+            } else {
+                self.birth_counts.contains(&0)
+            };
+        if !inverted && !next_inverted || self.states > 2 {
+            return (self.clone(), false);
+        }
+
+        // A cell stored as dead under inversion is alive, with as many live neighbors as the stored cell has dead
+        // ones, so it follows the survival counts instead of the birth counts and the other way around
+        let (birth, survival) = (
+            (&self.birth_counts, self.isotropic.map(|isotropic| isotropic.birth)),
+            (
+                &self.survival_counts,
+                self.isotropic.map(|isotropic| isotropic.survival),
+            ),
+        );
+        let (birth, survival) = if inverted { (survival, birth) } else { (birth, survival) };
+        // Letter bits for every count, with a single bit standing for all arrangements of a totalistic count
+        let every_letter = |count| {
+            if self.isotropic.is_some() {
+                all_letters(count)
+            } else {
+                1
+            }
+        };
+        let emulate = |(counts, letters): (&Vec<usize>, Option<[u16; 9]>)| -> Vec<u16> {
+            (0..=max_count)
+                .map(|count| {
+                    let count_before = if inverted { max_count - count } else { count };
+                    let matching = match letters {
+                        _ if !counts.contains(&count_before) => 0,
+                        Some(letters) if inverted => complement_letters(count_before, letters[count_before]),
+                        Some(letters) => letters[count_before],
+                        None => 1,
+                    };
+                    if next_inverted {
+                        every_letter(count) & !matching
+                    } else {
+                        matching
+                    }
+                })
+                .collect()
+        };
+        let (birth, survival) = (emulate(birth), emulate(survival));
+        let counts = |letters: &[u16]| (0..letters.len()).filter(|&count| letters[count] != 0).collect();
+        let rules = Rules {
+            birth_counts: counts(&birth),
+            survival_counts: counts(&survival),
+            isotropic: self.isotropic.map(|_| Isotropic {
+                birth: std::array::from_fn(|count| birth[count]),
+                survival: std::array::from_fn(|count| survival[count]),
+            }),
+            ..self.clone()
+        };
+        (rules, next_inverted)
+    }
+
+    /// Whether a live cell is next alive, for each of the 512 ways the 3x3 block around a cell can be filled.
+    /// Bit `3 * (dy + 1) + dx + 1` of the index holds the cell at offset `(dx, dy)`, with y pointing up, so bit 4
+    /// is the cell itself. Only meaningful for rules over the 3x3 neighborhood.
+    pub fn transition_table(&self) -> [bool; 512] {
+        let neighbors = match self.neighborhood {
+            Neighborhood::Moore => 0x1ff & !CENTER,
+            Neighborhood::VonNeumann => N | E | S | W,
+            Neighborhood::Hexagonal => 0x1ff & !(CENTER | NW | SE),
+        };
+        std::array::from_fn(|block| {
+            let block = block as u16;
+            let count = (block & neighbors).count_ones() as usize;
+            let (counts, letters) = if block & CENTER != 0 {
+                (
+                    &self.survival_counts,
+                    self.isotropic.map(|isotropic| isotropic.survival),
+                )
+            } else {
+                (&self.birth_counts, self.isotropic.map(|isotropic| isotropic.birth))
+            };
+            counts.contains(&count)
+                && letters.is_none_or(|letters| letters[count] & 1 << hensel_letter(block & neighbors) != 0)
+        })
+    }
+
+    /// Formats as a Larger than Life rulestring, with one `S` or `B` interval per run of consecutive counts
+    fn fmt_larger_than_life(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let states = if self.states > 2 { self.states } else { 0 };
+        write!(f, "R{},C{},M{}", self.range, states, self.include_center as u8)?;
+        for (tag, counts) in [('S', &self.survival_counts), ('B', &self.birth_counts)] {
+            let mut i = 0;
+            while i < counts.len() {
+                let run = counts[i..]
+                    .iter()
+                    .zip(counts[i]..)
+                    .take_while(|(&count, n)| count == *n)
+                    .count();
+                write!(f, ",{}{}..{}", tag, counts[i], counts[i + run - 1])?;
+                i += run;
+            }
+        }
+        let shape = match self.neighborhood {
+            Neighborhood::Moore => 'M',
+            Neighborhood::VonNeumann => 'N',
+            Neighborhood::Hexagonal => 'H',
+        };
+        write!(f, ",N{}", shape)
+    }
+
     #[allow(dead_code)]
     fn conway() -> Self {
         Self {
             survival_counts: vec![2, 3],
             birth_counts: vec![3],
+            neighborhood: Neighborhood::Moore,
+            states: 2,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
         }
     }
     #[allow(dead_code)]
@@ -93,6 +848,13 @@ impl Rules {
         Self {
             survival_counts: vec![2, 3],
             birth_counts: vec![3, 6], // Additional birth condition: 6 neighbors
+            neighborhood: Neighborhood::Moore,
+            states: 2,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
         }
     }
     #[allow(dead_code)]
@@ -100,6 +862,13 @@ impl Rules {
         Self {
             survival_counts: vec![3, 4, 6, 7, 8],
             birth_counts: vec![3, 6, 7, 8], // Birth and survival counts are similar
+            neighborhood: Neighborhood::Moore,
+            states: 2,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
         }
     }
     #[allow(dead_code)]
@@ -107,6 +876,13 @@ impl Rules {
         Self {
             survival_counts: vec![], // No survival counts; all live cells die
             birth_counts: vec![2],   // Cells are born with exactly 2 neighbors
+            neighborhood: Neighborhood::Moore,
+            states: 2,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
         }
     }
     #[allow(dead_code)]
@@ -114,6 +890,13 @@ impl Rules {
         Self {
             survival_counts: vec![1, 2, 3, 4, 5, 6, 7, 8], // Cells stay alive no matter their neighbors
             birth_counts: vec![3],                         // Standard birth condition
+            neighborhood: Neighborhood::Moore,
+            states: 2,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
         }
     }
     #[allow(dead_code)]
@@ -121,6 +904,13 @@ impl Rules {
         Self {
             survival_counts: vec![1, 2, 3, 4, 5],
             birth_counts: vec![3],
+            neighborhood: Neighborhood::Moore,
+            states: 2,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
         }
     }
     #[allow(dead_code)]
@@ -128,18 +918,39 @@ impl Rules {
         Self {
             survival_counts: vec![4, 6, 7, 8],
             birth_counts: vec![3, 5, 6, 7, 8],
+            neighborhood: Neighborhood::Moore,
+            states: 2,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
         }
     }
     fn diamoeba() -> Self {
         Self {
             survival_counts: vec![5, 6, 7, 8],
             birth_counts: vec![3, 5, 6, 7, 8],
+            neighborhood: Neighborhood::Moore,
+            states: 2,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
         }
     }
     fn two_by_two() -> Self {
         Self {
             survival_counts: vec![1, 2, 5],
             birth_counts: vec![3, 6],
+            neighborhood: Neighborhood::Moore,
+            states: 2,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
         }
     }
 
@@ -147,24 +958,290 @@ impl Rules {
         Self {
             survival_counts: vec![2, 4, 5],
             birth_counts: vec![3, 6, 8],
+            neighborhood: Neighborhood::Moore,
+            states: 2,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
         }
     }
     fn replicator() -> Self {
         Self {
             survival_counts: vec![1, 3, 5, 7],
             birth_counts: vec![1, 3, 5, 7],
+            neighborhood: Neighborhood::Moore,
+            states: 2,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
         }
     }
     fn fredkin() -> Self {
         Self {
             survival_counts: vec![0, 2, 4, 6, 8],
             birth_counts: vec![1, 3, 5, 7],
+            neighborhood: Neighborhood::Moore,
+            states: 2,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
         }
     }
     fn stains() -> Self {
         Self {
             survival_counts: vec![2, 3, 5, 6],
             birth_counts: vec![3, 6, 7, 8],
+            neighborhood: Neighborhood::Moore,
+            states: 2,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
+        }
+    }
+    fn brians_brain() -> Self {
+        Self {
+            survival_counts: vec![],
+            birth_counts: vec![2],
+            neighborhood: Neighborhood::Moore,
+            states: 3, // Firing cells always spend one generation refractory
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
+        }
+    }
+    fn star_wars() -> Self {
+        Self {
+            survival_counts: vec![3, 4, 5],
+            birth_counts: vec![2],
+            neighborhood: Neighborhood::Moore,
+            states: 4,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
+        }
+    }
+    fn frogs() -> Self {
+        Self {
+            survival_counts: vec![1, 2],
+            birth_counts: vec![3, 4],
+            neighborhood: Neighborhood::Moore,
+            states: 3,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
+        }
+    }
+    fn sticks() -> Self {
+        Self {
+            survival_counts: vec![3, 4, 5, 6],
+            birth_counts: vec![2],
+            neighborhood: Neighborhood::Moore,
+            states: 6,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
+        }
+    }
+    /// Larger than Life's best known rule, home to the "bosco" glider
+    fn bosco() -> Self {
+        Self::larger_than_life(5, true, 34..=58, 34..=45)
+    }
+    fn majority() -> Self {
+        Self::larger_than_life(4, true, 41..=81, 41..=81)
+    }
+    fn waffle() -> Self {
+        Self::larger_than_life(7, true, 100..=200, 75..=170)
+    }
+    fn globe() -> Self {
+        Self::larger_than_life(8, false, 163..=223, 74..=252)
+    }
+    fn critters() -> Self {
+        Self::margolus([15, 14, 13, 3, 11, 5, 6, 1, 7, 9, 10, 2, 12, 4, 8, 0])
+    }
+    fn tron() -> Self {
+        Self::margolus([15, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 0])
+    }
+    fn billiard_ball_machine() -> Self {
+        Self::margolus([0, 8, 4, 3, 2, 5, 9, 7, 1, 6, 10, 11, 12, 13, 14, 15])
+    }
+    fn single_rotation() -> Self {
+        Self::margolus([0, 2, 8, 3, 1, 5, 6, 7, 4, 9, 10, 11, 12, 13, 14, 15])
+    }
+
+    fn wireworld() -> Self {
+        Self {
+            survival_counts: Vec::new(),
+            birth_counts: Vec::new(),
+            neighborhood: Neighborhood::Moore,
+            states: 4,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: None,
+            wireworld: true,
+        }
+    }
+
+    /// Margolus block rules from a table of what each 2x2 block turns into
+    fn margolus(table: [u8; 16]) -> Self {
+        Self {
+            survival_counts: Vec::new(),
+            birth_counts: Vec::new(),
+            neighborhood: Neighborhood::Moore,
+            states: 2,
+            range: 1,
+            include_center: false,
+            isotropic: None,
+            margolus: Some(table),
+            wireworld: false,
         }
     }
+
+    /// Two-state Moore-neighborhood Larger than Life rules with a single survival and birth interval
+    fn larger_than_life(
+        range: usize,
+        include_center: bool,
+        survival: std::ops::RangeInclusive<usize>,
+        birth: std::ops::RangeInclusive<usize>,
+    ) -> Self {
+        Self {
+            survival_counts: survival.collect(),
+            birth_counts: birth.collect(),
+            neighborhood: Neighborhood::Moore,
+            states: 2,
+            range,
+            include_center,
+            isotropic: None,
+            margolus: None,
+            wireworld: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_canonically() {
+        for (rulestring, canonical) in [
+            ("23/3", "B3/S23"),
+            ("b36/s23", "B36/S23"),
+            ("B3/S23/C2", "B3/S23"),
+            ("B2cekin/S12", "B2-a/S12"),
+            ("B3aceijkn4a/S", "B3-yqr4a/S"),
+            ("/2/3", "B2/S/C3"),
+            ("345/2/4", "B2/S345/C4"),
+            ("B2/S013V", "B2/S013V"),
+            ("B2/S34H", "B2/S34H"),
+            ("R1,C0,M0,S2..3,B3,NM", "B3/S23"),
+            ("R2,C3,M0,S2..5,B3..4,NN", "R2,C3,M0,S2..5,B3..4,NN"),
+            ("R3,C0,M1,S10..20,NH", "R3,C0,M1,S10..20,NH"),
+            (
+                "ms,d0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15",
+                "MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15",
+            ),
+            ("wireworld", "WireWorld"),
+        ] {
+            assert_eq!(
+                rulestring.parse::<Rules>().unwrap().to_string(),
+                canonical,
+                "{rulestring}"
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips_through_display() {
+        for rulestring in [
+            "B3/S23",
+            "B0123478/S34678",
+            "B2-a/S12",
+            "B2ce3-q/S1c23-ak4z",
+            "B2/S/C3",
+            "B3/S23/C256V",
+            "B2/S34/C5H",
+            "R5,C0,M1,S34..58,B34..45,NM",
+            "R2,C4,M1,S5..9,B4..6,NN",
+            "MS,D15;1;2;3;4;5;6;7;8;9;10;11;12;13;14;0",
+            "WireWorld",
+        ] {
+            let rules: Rules = rulestring.parse().unwrap();
+            assert_eq!(rules.to_string().parse::<Rules>(), Ok(rules), "{rulestring}");
+        }
+        for &rule_set in RuleSet::value_variants() {
+            let rulestring = rule_set.to_rules().to_string();
+            assert_eq!(
+                SelectedRules::from(rulestring.parse::<Rules>().unwrap()),
+                SelectedRules::Preset(rule_set),
+                "{rulestring}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_rulestrings() {
+        for rulestring in [
+            "B3",
+            "B9/S",
+            "B3/S23/C1",
+            "B3/S23/C257",
+            "B3x/S",
+            "B2a/S2V",
+            "B2-/S",
+            "R0,C0,M1,S1,B1,NM",
+            "R2,S30..10,NM",
+            "R2,NX",
+            "MS,D1;2;3",
+            "MS,D16;0;0;0;0;0;0;0;0;0;0;0;0;0;0;0",
+        ] {
+            assert!(rulestring.parse::<Rules>().is_err(), "{rulestring}");
+        }
+    }
+
+    #[test]
+    fn hensel_letters_pick_arrangements() {
+        let table = "B2c3-q/S".parse::<Rules>().unwrap().transition_table();
+        assert!(table[(NW | NE) as usize]); // 2c
+        assert!(!table[(N | NE) as usize]); // 2a
+        assert!(!table[(NE | SW) as usize]); // 2n
+        assert!(table[(N | E | W) as usize]); // 3e
+        assert!(!table[(N | NE | SW) as usize]); // 3q
+                                                 // The same arrangement turned and mirrored
+        assert!(!table[(S | SW | NE) as usize]);
+        assert!(!table[(N | NW | SE) as usize]);
+        assert!(!table[(N | NE | CENTER) as usize]);
+    }
+
+    #[test]
+    fn b0_emulation_keeps_the_background_dead() {
+        // Without S8 the real background flips every generation, so the stored one is inverted every other time
+        let emulated = |rulestring: &str, inverted| {
+            let (rules, next_inverted) = rulestring.parse::<Rules>().unwrap().b0_emulation(inverted);
+            (rules.to_string(), next_inverted)
+        };
+        assert_eq!(emulated("B0/S", false), ("B12345678/S012345678".into(), true));
+        assert_eq!(emulated("B0/S", true), ("B/S8".into(), false));
+        // With S8 it stays alive, so every generation after the first is inverted
+        assert_eq!(emulated("B0123478/S34678", false), ("B56/S0125".into(), true));
+        assert_eq!(emulated("B0123478/S34678", true), ("B3678/S23".into(), true));
+        // Rules without B0 run as they are
+        assert_eq!(emulated("B3/S23", false), ("B3/S23".into(), false));
+    }
 }
@@ -0,0 +1,293 @@
+use crate::grid::{count_mask, lookup_transitions, NeighborCounts};
+use crate::hashlife::HashLife;
+use crate::patterns::Board;
+use crate::rules::{Neighborhood, Rules};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Cells along each side of a chunk, one row per `u64`
+const CHUNK_SIZE: i64 = 64;
+
+/// 64 x 64 cells, one word per row from the bottom up; bit `i` of a row is the cell `i` columns from the left
+type Chunk = [u64; CHUNK_SIZE as usize];
+
+/// An unbounded universe stored as a map of 64 x 64 chunks, holding only the chunks that have live cells.
+///
+/// Each generation only visits the live chunks and the chunks bordering them, so the cost follows the amount
+/// of live area rather than how far apart the cells have spread. Like `HashLife`, it only runs two-state 3x3
+/// rules without B0.
+#[derive(Clone, Default)]
+pub struct SparseUniverse {
+    chunks: HashMap<(i64, i64), Chunk>,
+}
+
+impl SparseUniverse {
+    /// Creates an empty universe
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_alive(&self, x: i64, y: i64) -> bool {
+        let ((chunk_x, chunk_y), (column, row)) = split(x, y);
+        self.chunks
+            .get(&(chunk_x, chunk_y))
+            .is_some_and(|chunk| chunk[row] & 1 << column != 0)
+    }
+
+    pub fn set_alive(&mut self, x: i64, y: i64, is_alive: bool) {
+        let (key, (column, row)) = split(x, y);
+        if is_alive {
+            self.chunks.entry(key).or_insert([0; CHUNK_SIZE as usize])[row] |= 1 << column;
+        } else if let Some(chunk) = self.chunks.get_mut(&key) {
+            chunk[row] &= !(1 << column);
+            if chunk.iter().all(|&row| row == 0) {
+                self.chunks.remove(&key);
+            }
+        }
+    }
+
+    /// Number of live cells
+    pub fn population(&self) -> u64 {
+        self.chunks.values().flatten().map(|row| row.count_ones() as u64).sum()
+    }
+
+    /// Makes every cell dead
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    /// Advances the universe by one generation.
+    ///
+    /// Panics if the rules contain B0, which would fill the infinite empty background, dying states or a larger
+    /// neighborhood.
+    pub fn step(&mut self, rules: &Rules) {
+        assert!(
+            HashLife::supports(rules),
+            "the sparse universe only runs two-state 3x3 rules without B0"
+        );
+        let birth = count_mask(&rules.birth_counts);
+        let survival = count_mask(&rules.survival_counts);
+        let transitions = rules.isotropic.is_some().then(|| rules.transition_table());
+
+        // A live chunk can only spread into a neighbor through the cells along the edge they share
+        let mut candidates = HashSet::with_capacity(self.chunks.len() * 2);
+        for (&(chunk_x, chunk_y), chunk) in &self.chunks {
+            let columns = chunk.iter().fold(0, |columns, row| columns | row);
+            let west = columns & 1 != 0;
+            let east = columns >> 63 != 0;
+            let south = chunk[0] != 0;
+            let north = chunk[CHUNK_SIZE as usize - 1] != 0;
+            for (dx, dy, spreads) in [
+                (0, 0, true),
+                (-1, 0, west),
+                (1, 0, east),
+                (0, -1, south),
+                (0, 1, north),
+                (-1, -1, chunk[0] & 1 != 0),
+                (1, -1, chunk[0] >> 63 != 0),
+                (-1, 1, chunk[CHUNK_SIZE as usize - 1] & 1 != 0),
+                (1, 1, chunk[CHUNK_SIZE as usize - 1] >> 63 != 0),
+            ] {
+                if spreads {
+                    candidates.insert((chunk_x + dx, chunk_y + dy));
+                }
+            }
+        }
+        self.chunks = candidates
+            .into_par_iter()
+            .filter_map(|key| {
+                let next = self.next_chunk(key, birth, survival, rules.neighborhood, transitions.as_ref());
+                next.iter().any(|&row| row != 0).then_some((key, next))
+            })
+            .collect();
+    }
+
+    /// Smallest and largest coordinates of live cells, as `(min_x, min_y, max_x, max_y)`
+    pub fn live_bounds(&self) -> Option<(i64, i64, i64, i64)> {
+        self.chunks
+            .iter()
+            .map(|(&(chunk_x, chunk_y), chunk)| {
+                let columns = chunk.iter().fold(0, |columns, row| columns | row);
+                let min_row = chunk.iter().position(|&row| row != 0).unwrap_or(0) as i64;
+                let max_row = chunk.iter().rposition(|&row| row != 0).unwrap_or(0) as i64;
+                let (x, y) = (chunk_x * CHUNK_SIZE, chunk_y * CHUNK_SIZE);
+                (
+                    x + columns.trailing_zeros() as i64,
+                    y + min_row,
+                    x + 63 - columns.leading_zeros() as i64,
+                    y + max_row,
+                )
+            })
+            .reduce(|(min_x, min_y, max_x, max_y), (x0, y0, x1, y1)| {
+                (min_x.min(x0), min_y.min(y0), max_x.max(x1), max_y.max(y1))
+            })
+    }
+
+    /// Calls `f(column, row)` for every block of 2^`scale_log2` x 2^`scale_log2` cells containing a live cell,
+    /// within the `columns` x `rows` blocks whose bottom-left cell is `(x, y)`, possibly more than once per block
+    pub fn for_each_live_block(
+        &self,
+        x: i64,
+        y: i64,
+        columns: usize,
+        rows: usize,
+        scale_log2: u32,
+        mut f: impl FnMut(usize, usize),
+    ) {
+        let (end_x, end_y) = (x + ((columns as i64) << scale_log2), y + ((rows as i64) << scale_log2));
+        for (&(chunk_x, chunk_y), chunk) in &self.chunks {
+            let (left, bottom) = (chunk_x * CHUNK_SIZE, chunk_y * CHUNK_SIZE);
+            if left >= end_x || left + CHUNK_SIZE <= x || bottom >= end_y || bottom + CHUNK_SIZE <= y {
+                continue;
+            }
+            for (row, &bits) in chunk.iter().enumerate() {
+                let cell_y = bottom + row as i64;
+                if !(y..end_y).contains(&cell_y) {
+                    continue;
+                }
+                let mut bits = bits;
+                while bits != 0 {
+                    let cell_x = left + bits.trailing_zeros() as i64;
+                    bits &= bits - 1;
+                    if (x..end_x).contains(&cell_x) {
+                        f(
+                            ((cell_x - x) >> scale_log2) as usize,
+                            ((cell_y - y) >> scale_log2) as usize,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// The chunk at `key` one generation on, computed a row at a time from the words around it, and through the
+    /// transition table for isotropic non-totalistic rules
+    fn next_chunk(
+        &self,
+        (chunk_x, chunk_y): (i64, i64),
+        birth: u16,
+        survival: u16,
+        neighborhood: Neighborhood,
+        transitions: Option<&[bool; 512]>,
+    ) -> Chunk {
+        // Rows of the chunk and of the chunks to its west and east, plus one row past either end
+        let padded_column = |dx: i64| {
+            let chunk = |dy: i64| self.chunks.get(&(chunk_x + dx, chunk_y + dy));
+            let mut rows = [0; CHUNK_SIZE as usize + 2];
+            if let Some(below) = chunk(-1) {
+                rows[0] = below[CHUNK_SIZE as usize - 1];
+            }
+            if let Some(chunk) = chunk(0) {
+                rows[1..=CHUNK_SIZE as usize].copy_from_slice(chunk);
+            }
+            if let Some(above) = chunk(1) {
+                rows[CHUNK_SIZE as usize + 1] = above[0];
+            }
+            rows
+        };
+        let [west, middle, east] = [-1, 0, 1].map(padded_column);
+        // Cells of a padded row with each bit replaced by its left-hand, own and right-hand neighbor's
+        let neighbors = |row: usize| {
+            (
+                middle[row] << 1 | west[row] >> 63,
+                middle[row],
+                middle[row] >> 1 | east[row] << 63,
+            )
+        };
+
+        let mut next = [0; CHUNK_SIZE as usize];
+        for (row, next) in next.iter_mut().enumerate() {
+            let (below_left, below, below_right) = neighbors(row);
+            let (left, alive, right) = neighbors(row + 1);
+            let (above_left, above, above_right) = neighbors(row + 2);
+            if let Some(transitions) = transitions {
+                *next = lookup_transitions(
+                    transitions,
+                    &[
+                        below_left,
+                        below,
+                        below_right,
+                        left,
+                        alive,
+                        right,
+                        above_left,
+                        above,
+                        above_right,
+                    ],
+                );
+                continue;
+            }
+            let counts = match neighborhood {
+                Neighborhood::Moore => NeighborCounts::sum(&[
+                    below_left,
+                    below,
+                    below_right,
+                    left,
+                    right,
+                    above_left,
+                    above,
+                    above_right,
+                ]),
+                Neighborhood::VonNeumann => NeighborCounts::sum(&[below, left, right, above]),
+                Neighborhood::Hexagonal => NeighborCounts::sum(&[below_left, below, left, right, above, above_right]),
+            };
+            *next = alive & counts.matching(survival) | !alive & counts.matching(birth);
+        }
+        next
+    }
+}
+
+impl Board for SparseUniverse {
+    fn place_cell(&mut self, x: isize, y: isize) -> bool {
+        self.set_alive(x as i64, y as i64, true);
+        true
+    }
+}
+
+/// The chunk holding cell `(x, y)`, and the cell's column and row within it
+fn split(x: i64, y: i64) -> ((i64, i64), (usize, usize)) {
+    (
+        (x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE)),
+        (x.rem_euclid(CHUNK_SIZE) as usize, y.rem_euclid(CHUNK_SIZE) as usize),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::patterns::{Orientation, Pattern};
+    use crate::topology::Topology;
+
+    /// A bounded grid with an acorn in the middle, large enough that nothing reaches its edges for a while
+    fn acorn_grid() -> Grid {
+        let mut grid = Grid::new(256, 256);
+        grid.set_topology(Topology::Bounded);
+        Pattern::Acorn.add_to_grid(&mut grid, &[], Orientation::default(), 128, 128);
+        grid
+    }
+
+    /// Whether `alive` holds exactly the live cells of the grid, shifted so the grid's middle is at the origin
+    fn matches(grid: &Grid, alive: impl Fn(i64, i64) -> bool) -> bool {
+        (0..256).all(|y| (0..256).all(|x| alive(x as i64 - 128, y as i64 - 128) == grid.is_alive(x, y)))
+    }
+
+    #[test]
+    fn steps_like_grid() {
+        for rulestring in ["B3/S23", "B36/S23", "B2-a/S12", "B2/S013V", "B2/S34H"] {
+            let rules: Rules = rulestring.parse().unwrap();
+            let mut grid = acorn_grid();
+            let mut universe = SparseUniverse::new();
+            Pattern::Acorn.add_to_grid(&mut universe, &[], Orientation::default(), 0, 0);
+            for generation in 1..=40 {
+                universe.step(&rules);
+                grid.step(&rules);
+                assert_eq!(universe.population(), grid.population() as u64);
+                assert!(
+                    generation % 8 != 0 || matches(&grid, |x, y| universe.is_alive(x, y)),
+                    "{rulestring}, generation {generation}"
+                );
+            }
+        }
+    }
+}
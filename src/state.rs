@@ -1,18 +1,231 @@
-use crate::{config::Mode, patterns::Pattern, rules::RuleSet};
+use crate::config::Mode;
 use bevy::prelude::*;
+use game_of_life_bevy::grid::Grid;
+use game_of_life_bevy::history::History;
+use game_of_life_bevy::patterns::{LoadedPattern, Orientation, Pattern};
+use game_of_life_bevy::rules::{SelectedRules, CONDUCTOR, ELECTRON_HEAD, ELECTRON_TAIL};
+use game_of_life_bevy::universe::Universe;
+use std::path::PathBuf;
+
+/// Distance between rows of hexagonal cells, as a fraction of the cell size: √3/2, so the hexagons tile
+const HEX_ROW_SPACING: f32 = 0.866_025_4;
+
 #[derive(Resource)]
 pub struct GameState {
-    pub cells: Vec<Vec<Cell>>,
-    pub next_cells: Vec<Vec<bool>>,
+    pub grid: Grid,
+    /// Unbounded universe that is stepped and drawn instead of the grid when the hashlife or infinite engine is
+    /// selected
+    pub universe: Option<Universe>,
+    /// Keep the camera fitted to the live cells of the unbounded universe as they spread
+    pub follow_population: bool,
+    /// Each HashLife tick advances 2^step_exponent generations
+    pub step_exponent: u32,
+    /// Width and height of each cell sprite in pixels
+    pub cell_size: f32,
     pub mode: Mode,
+    /// Cells are laid out, drawn and clicked on as hexagons, following the hexagonal neighborhood of the rules.
+    /// Kept up to date by `update_layout`.
+    pub hexagonal: bool,
     pub selected_pattern: Pattern,
-    pub selected_rules: RuleSet,
+    pub pattern_orientation: Orientation,
+    /// State painted by holding the left mouse button, replacing pattern placement while one is chosen
+    pub brush: Option<u8>,
+    pub selected_rules: SelectedRules,
+    /// Generations only advance while unpaused, or one at a time with the step key
+    pub paused: bool,
+    /// Target rate for the fixed update schedule
+    pub ticks_per_second: f64,
+    /// Run as many generations per frame as fit in the frame budget instead of following `ticks_per_second`
+    pub fast_forward: bool,
+    /// Generations stepped since the app started
+    pub generation: u64,
+    /// Earlier grid states, for rewinding generations and undoing edits
+    pub history: History,
+    pub loaded_patterns: Vec<LoadedPattern>,
+    /// Switch to a loaded pattern's declared rules when placing it
+    pub use_pattern_rules: bool,
+    /// Where the grid is saved when exporting; `.cells` files are written as plaintext, anything else as RLE
+    pub export_path: PathBuf,
+}
+
+impl GameState {
+    /// Advances the grid by one generation with the selected rules
+    pub fn step(&mut self) {
+        let rules = self.selected_rules.to_rules();
+        if let Some(universe) = &mut self.universe {
+            self.generation += universe.step(&rules, self.step_exponent);
+            return;
+        }
+        self.history.record(&self.grid);
+        self.grid.step(&rules);
+        self.generation += 1;
+    }
+
+    /// Switches rules, unless the engine can't run them
+    pub fn set_rules(&mut self, selected_rules: SelectedRules) -> Result<(), String> {
+        if self.universe.is_some() && !Universe::supports(&selected_rules.to_rules()) {
+            return Err(format!(
+                "the unbounded universe can't run {}: it needs two-state square 3x3 rules without B0",
+                selected_rules
+            ));
+        }
+        self.selected_rules = selected_rules;
+        Ok(())
+    }
+
+    pub fn selected_pattern_name(&self) -> String {
+        match self.selected_pattern {
+            Pattern::Loaded(index) => self.loaded_patterns[index].name.clone(),
+            pattern => format!("{:?}", pattern),
+        }
+    }
+
+    /// Name of a cell state under the selected rules, e.g. for the brush
+    pub fn state_name(&self, state: u8) -> String {
+        let wireworld = self.selected_rules.to_rules().wireworld;
+        match state {
+            0 if wireworld => "Empty".to_string(),
+            ELECTRON_HEAD if wireworld => "Electron head".to_string(),
+            ELECTRON_TAIL if wireworld => "Electron tail".to_string(),
+            CONDUCTOR if wireworld => "Conductor".to_string(),
+            0 => "Dead".to_string(),
+            1 => "Alive".to_string(),
+            state => format!("Dying ({})", state),
+        }
+    }
+
+    /// Selected pattern name followed by its rotation and mirroring, if any
+    pub fn selected_pattern_description(&self) -> String {
+        let orientation = self.pattern_orientation;
+        let mut description = self.selected_pattern_name();
+        match (orientation.quarter_turns, orientation.mirrored) {
+            (0, false) => {}
+            (0, true) => description.push_str(" (mirrored)"),
+            (turns, false) => description.push_str(&format!(" ({}°)", turns as u32 * 90)),
+            (turns, true) => description.push_str(&format!(" ({}°, mirrored)", turns as u32 * 90)),
+        }
+        description
+    }
+
+    /// Cell containing a world position, which may lie off the grid
+    pub fn world_to_cell(&self, world_pos: Vec2) -> (isize, isize) {
+        let half_grid = Vec2::new(self.grid.width() as f32, self.grid.height() as f32) / 2.0;
+        if self.hexagonal {
+            // Undo the shear of `cell_position`, then find the hexagon whose center is nearest
+            let y = world_pos.y / (self.cell_size * HEX_ROW_SPACING);
+            let x = world_pos.x / self.cell_size + y / 2.0;
+            return hex_round(x + half_grid.x, y + half_grid.y);
+        }
+        // Cell sprites are centered on `cell_position`, so shift by half a cell before rounding down
+        let cell = (world_pos / self.cell_size + half_grid + 0.5).floor();
+        (cell.x as isize, cell.y as isize)
+    }
+
+    /// Grid cell containing a world position, or `None` if it is off the grid
+    pub fn world_to_grid(&self, world_pos: Vec2) -> Option<(usize, usize)> {
+        let (x, y) = self.world_to_cell(world_pos);
+        let in_bounds = (0..self.grid.width() as isize).contains(&x) && (0..self.grid.height() as isize).contains(&y);
+        in_bounds.then_some((x as usize, y as usize))
+    }
+
+    /// Cell a click at a world position places the selected pattern at: anywhere in the unbounded universe, or
+    /// only on the grid
+    pub fn placement_cell(&self, world_pos: Vec2) -> Option<(isize, isize)> {
+        if self.universe.is_some() {
+            Some(self.world_to_cell(world_pos))
+        } else {
+            self.world_to_grid(world_pos).map(|(x, y)| (x as isize, y as isize))
+        }
+    }
+
+    /// World position of the center of the grid, midway between its first and last cells; with square cells it
+    /// is half a cell down and left of the origin
+    pub fn grid_center(&self) -> Vec2 {
+        let (width, height) = (self.grid.width() as isize, self.grid.height() as isize);
+        (self.cell_position(0, 0) + self.cell_position(width - 1, height - 1)) / 2.0
+    }
+
+    /// World width and height of the box around the whole grid, which is a parallelogram with hexagonal cells
+    pub fn grid_size(&self) -> Vec2 {
+        let (width, height) = (self.grid.width() as isize, self.grid.height() as isize);
+        let bottom_right = self.cell_position(width - 1, 0);
+        let top_left = self.cell_position(0, height - 1);
+        Vec2::new(bottom_right.x - top_left.x, top_left.y - bottom_right.y) + self.cell_size
+    }
+
+    /// Distance from the center of a hexagonal cell to its corners, so neighboring hexagons just touch
+    pub fn hex_radius(&self) -> f32 {
+        self.cell_size / 3f32.sqrt()
+    }
+
+    /// World position of the center of the cell at `(x, y)`, which may lie off the grid. Rows of hexagonal cells
+    /// are packed closer together and each is shifted half a cell left of the one below, so a cell's six
+    /// neighbors surround it.
+    pub fn cell_position(&self, x: isize, y: isize) -> Vec2 {
+        let x = x as f32 - self.grid.width() as f32 / 2.0;
+        let y = y as f32 - self.grid.height() as f32 / 2.0;
+        if self.hexagonal {
+            Vec2::new(x - y / 2.0, y * HEX_ROW_SPACING) * self.cell_size
+        } else {
+            Vec2::new(x, y) * self.cell_size
+        }
+    }
+}
+
+/// Rounds a fractional hexagonal cell position to the cell it falls in. The rounding is done on three axes, `x`,
+/// `y` and `x - y`, and the one that moved furthest is recomputed from the other two so they agree again.
+fn hex_round(x: f32, y: f32) -> (isize, isize) {
+    let z = x - y;
+    let (mut round_x, mut round_y, round_z) = (x.round(), y.round(), z.round());
+    let (dx, dy, dz) = ((round_x - x).abs(), (round_y - y).abs(), (round_z - z).abs());
+    if dx > dy && dx > dz {
+        round_x = round_y + round_z;
+    } else if dy > dz {
+        round_y = round_x - round_z;
+    }
+    (round_x as isize, round_y as isize)
+}
+
+/// In-app rulestring entry box, opened and submitted with Enter
+#[derive(Resource, Default)]
+pub struct RuleEntry {
+    pub active: bool,
+    pub buffer: String,
+    pub error: Option<String>,
 }
 
-#[derive(Clone)]
-pub struct Cell {
-    pub is_alive: bool,
-    pub activation_count: u32,
+/// Marks the single sprite that shows the grid texture in color mode
+#[derive(Component)]
+pub struct GridSprite;
+
+/// Marks the mesh of hexagons that shows the grid in color mode when cells are hexagonal
+#[derive(Component)]
+pub struct HexGridMesh;
+
+/// Marks the sprite showing the visible part of the unbounded universe
+#[derive(Component)]
+pub struct UniverseSprite;
+
+/// Marks the sprite drawing the cell at `(x, y)` in image mode
+#[derive(Component)]
+pub struct CellSprite {
+    pub x: usize,
+    pub y: usize,
+}
+
+/// Marks a translucent sprite previewing where the selected pattern will be placed
+#[derive(Component)]
+pub struct GhostCell;
+
+/// What the ghost sprites were last spawned for, so they are only rebuilt when something changes
+#[derive(Clone, Copy, PartialEq)]
+pub struct PatternPreview {
+    pub x: isize,
+    pub y: isize,
+    pub pattern: Pattern,
+    pub orientation: Orientation,
+    pub grid_size: (usize, usize),
+    pub hexagonal: bool,
 }
 
 #[derive(Component)]
@@ -21,8 +234,37 @@ pub struct SelectedPatternText;
 #[derive(Component)]
 pub struct SelectedRulesText;
 
+#[derive(Component)]
+pub struct RuleEntryText;
+
+#[derive(Component)]
+pub struct PausedText;
+
+#[derive(Component)]
+pub struct SpeedText;
+
+/// Measures how many generations per second are actually being stepped
+#[derive(Resource, Default)]
+pub struct TickCounter {
+    pub last_generation: u64,
+    pub elapsed: f32,
+    pub achieved_ticks_per_second: f64,
+}
+
 #[derive(Resource, Clone)]
 pub struct Textures {
     pub alive_texture: Handle<Image>,
     pub dead_texture: Handle<Image>,
+    /// One pixel per cell, rewritten every tick in color mode
+    pub grid_texture: Handle<Image>,
+    /// The visible part of the unbounded universe, rewritten every frame
+    pub universe_texture: Handle<Image>,
+    /// One hexagon per cell, whose vertex colors are rewritten every tick in color mode with hexagonal cells
+    pub hex_grid_mesh: Handle<Mesh>,
+    /// A single hexagonal cell, for previewing patterns
+    pub hex_cell_mesh: Handle<Mesh>,
+    /// Plain white, so the hexagon meshes show their vertex colors
+    pub hex_material: Handle<ColorMaterial>,
+    /// Translucent, for previewing patterns with hexagonal cells
+    pub ghost_material: Handle<ColorMaterial>,
 }
@@ -1,60 +1,55 @@
 use crate::config::Mode;
-use crate::state::{Cell, SelectedPatternText, SelectedRulesText, Textures};
+use crate::state::{
+    CellSprite, GhostCell, GridSprite, HexGridMesh, PatternPreview, PausedText, RuleEntry, RuleEntryText,
+    SelectedPatternText, SelectedRulesText, SpeedText, Textures, TickCounter, UniverseSprite,
+};
 use crate::{config, state::GameState};
+use bevy::color::{ColorToPacked, Mix};
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel};
 use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology, VertexAttributeValues};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::texture::ImageSampler;
+use bevy::sprite::{Anchor, MaterialMesh2dBundle};
+use bevy::utils::Instant;
 use bevy::window::PrimaryWindow;
+use game_of_life_bevy::patterns::{Orientation, Pattern};
+use game_of_life_bevy::rules::{Neighborhood, ParseRulesError, Rules, SelectedRules};
+use game_of_life_bevy::universe::Universe;
 use rayon::prelude::*;
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_3};
 
-pub fn setup(mut commands: Commands, game_state: ResMut<GameState>, asset_server: Res<AssetServer>) {
+pub fn setup(
+    mut commands: Commands,
+    game_state: ResMut<GameState>,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
     // Spawn the 2D camera
     commands.spawn(Camera2dBundle::default());
 
-    // Load textures for alive and dead cells
+    // Load textures for alive and dead cells, and create the texture (or hexagon mesh) the whole grid is drawn into
     let textures = Textures {
         alive_texture: asset_server.load(config::ALIVE_IMAGE),
         dead_texture: asset_server.load(config::DEAD_IMAGE),
+        grid_texture: images.add(grid_image(game_state.grid.width(), game_state.grid.height())),
+        universe_texture: images.add(grid_image(1, 1)),
+        hex_grid_mesh: meshes.add(hex_grid_mesh(&game_state)),
+        hex_cell_mesh: meshes.add(RegularPolygon::new(game_state.hex_radius(), 6)),
+        hex_material: materials.add(ColorMaterial::default()),
+        ghost_material: materials.add(ColorMaterial::from(config::GHOST_COLOR)),
     };
     commands.insert_resource(textures.clone());
 
-    // Spawn a grid of sprites, either using color or texture mode based on the config
-    for y in 0..crate::config::GRID_HEIGHT {
-        for x in 0..crate::config::GRID_WIDTH {
-            if game_state.mode == Mode::Color {
-                // Color mode: spawn with color
-                commands.spawn(SpriteBundle {
-                    sprite: Sprite {
-                        color: config::DEAD_COLOR,
-                        custom_size: Some(Vec2::splat(config::CELL_SIZE)),
-                        ..Default::default()
-                    },
-                    transform: Transform::from_xyz(
-                        x as f32 * config::CELL_SIZE - config::GRID_WIDTH as f32 * config::CELL_SIZE / 2.0,
-                        y as f32 * config::CELL_SIZE - config::GRID_HEIGHT as f32 * config::CELL_SIZE / 2.0,
-                        0.0,
-                    ),
-                    ..Default::default()
-                });
-            } else {
-                // Image mode: spawn with texture
-                commands.spawn(SpriteBundle {
-                    texture: textures.dead_texture.clone(),
-                    sprite: Sprite {
-                        custom_size: Some(Vec2::splat(config::CELL_SIZE)), // Limit sprite to cell size
-                        ..Default::default()
-                    },
-                    transform: Transform::from_xyz(
-                        x as f32 * config::CELL_SIZE - config::GRID_WIDTH as f32 * config::CELL_SIZE / 2.0,
-                        y as f32 * config::CELL_SIZE - config::GRID_HEIGHT as f32 * config::CELL_SIZE / 2.0,
-                        0.0,
-                    ),
-                    ..Default::default()
-                });
-            }
-        }
-    }
+    spawn_cells(&mut commands, &game_state, &textures);
+
     commands.spawn((
         TextBundle::from_section(
-            format!("Selected Pattern: {:?}", game_state.selected_pattern),
+            format!("Selected Pattern: {}", game_state.selected_pattern_description()),
             TextStyle {
                 font: asset_server.load(config::FONT),
                 font_size: 24.0,
@@ -74,7 +69,7 @@ pub fn setup(mut commands: Commands, game_state: ResMut<GameState>, asset_server
 
     commands.spawn((
         TextBundle::from_section(
-            format!("Rules: {:?}", game_state.selected_rules),
+            format!("Rules: {}", game_state.selected_rules),
             TextStyle {
                 font: asset_server.load(config::FONT),
                 font_size: 24.0,
@@ -91,137 +86,879 @@ pub fn setup(mut commands: Commands, game_state: ResMut<GameState>, asset_server
         }),
         SelectedRulesText, // Marker component
     ));
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load(config::FONT),
+                font_size: 24.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            width: Val::Px(200.0),
+            height: Val::Px(100.0),
+            right: Val::Px(10.0),
+            bottom: Val::Px(160.0),
+            ..Default::default()
+        }),
+        RuleEntryText, // Marker component
+    ));
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load(config::FONT),
+                font_size: 24.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            top: Val::Px(10.0),
+            ..Default::default()
+        }),
+        PausedText, // Marker component
+    ));
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load(config::FONT),
+                font_size: 24.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            top: Val::Px(40.0),
+            ..Default::default()
+        }),
+        SpeedText, // Marker component
+    ));
+}
+
+/// Spawns the sprites that draw the grid: in color mode a single sprite showing the grid texture, or the mesh of
+/// hexagons for hexagonal cells, and in image mode one textured sprite per cell. The unbounded universe is always
+/// drawn as a single sprite.
+fn spawn_cells(commands: &mut Commands, game_state: &GameState, textures: &Textures) {
+    let cell_size = game_state.cell_size;
+    let width = game_state.grid.width();
+    let height = game_state.grid.height();
+
+    if game_state.universe.is_some() {
+        commands.spawn((
+            SpriteBundle {
+                texture: textures.universe_texture.clone(),
+                sprite: Sprite {
+                    anchor: Anchor::BottomLeft, // Placed at the corner of the visible region by `render_universe`
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            UniverseSprite,
+        ));
+        return;
+    }
+
+    if game_state.mode == Mode::Color && game_state.hexagonal {
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: textures.hex_grid_mesh.clone().into(),
+                material: textures.hex_material.clone(),
+                ..Default::default()
+            },
+            HexGridMesh,
+        ));
+        return;
+    }
+
+    if game_state.mode == Mode::Color {
+        commands.spawn((
+            SpriteBundle {
+                texture: textures.grid_texture.clone(),
+                sprite: Sprite {
+                    custom_size: Some(game_state.grid_size()),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(game_state.grid_center().extend(0.0)),
+                ..Default::default()
+            },
+            GridSprite,
+        ));
+        return;
+    }
+
+    for y in 0..height {
+        for x in 0..width {
+            // Image mode: spawn with texture
+            commands.spawn((
+                SpriteBundle {
+                    texture: textures.dead_texture.clone(),
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::splat(cell_size)), // Limit sprite to cell size
+                        ..Default::default()
+                    },
+                    transform: Transform::from_translation(
+                        game_state.cell_position(x as isize, y as isize).extend(0.0),
+                    ),
+                    ..Default::default()
+                },
+                CellSprite { x, y },
+            ));
+        }
+    }
+}
+
+/// A texture with one pixel per cell, all dead, drawn without smoothing so cells stay crisp when zoomed
+fn grid_image(width: usize, height: usize) -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &config::DEAD_COLOR.to_srgba().to_u8_array(),
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+/// A mesh with one pointy-topped hexagon per cell, centered on `cell_position`, and a vertex color for each of its
+/// six corners. Cells follow each other row by row from the bottom, all dead.
+fn hex_grid_mesh(game_state: &GameState) -> Mesh {
+    let (width, height) = (game_state.grid.width(), game_state.grid.height());
+    // Corners counterclockwise from the top
+    let corners: Vec<Vec2> = (0..6)
+        .map(|k| Vec2::from_angle(FRAC_PI_2 + k as f32 * FRAC_PI_3) * game_state.hex_radius())
+        .collect();
+    let mut positions = Vec::with_capacity(width * height * 6);
+    let mut indices = Vec::with_capacity(width * height * 12);
+    for y in 0..height {
+        for x in 0..width {
+            let center = game_state.cell_position(x as isize, y as isize);
+            let first = positions.len() as u32;
+            positions.extend(corners.iter().map(|&corner| (center + corner).extend(0.0).to_array()));
+            // A fan of four triangles from the top corner
+            indices.extend((1..5).flat_map(|k| [first, first + k, first + k + 1]));
+        }
+    }
+    let colors = vec![config::DEAD_COLOR.to_linear().to_f32_array(); positions.len()];
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Grows or shrinks the board, keeping the existing cells centered, and respawns the cell sprites
+pub fn resize_grid(
+    mut commands: Commands,
+    mut game_state: ResMut<GameState>,
+    textures: Res<Textures>,
+    mut images: ResMut<Assets<Image>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    cells: Query<Entity, CellEntity>,
+) {
+    let step = if keys.just_pressed(KeyCode::PageUp) {
+        config::GRID_RESIZE_STEP as isize
+    } else if keys.just_pressed(KeyCode::PageDown) {
+        -(config::GRID_RESIZE_STEP as isize)
+    } else {
+        return;
+    };
+
+    let width = (game_state.grid.width() as isize + step).max(config::MIN_GRID_SIZE as isize) as usize;
+    let height = (game_state.grid.height() as isize + step).max(config::MIN_GRID_SIZE as isize) as usize;
+    game_state.grid.resize(width, height);
+    game_state.history.clear(); // Saved frames no longer match the grid size
+
+    images.insert(&textures.grid_texture, grid_image(width, height));
+    for entity in &cells {
+        commands.entity(entity).despawn();
+    }
+    spawn_cells(&mut commands, &game_state, &textures);
+}
+
+/// The sprites or mesh drawing the grid, whichever `spawn_cells` chose
+type CellEntity = Or<(With<CellSprite>, With<GridSprite>, With<HexGridMesh>)>;
+
+/// Switches between square and hexagonal cells when the rules' neighborhood changes, and respawns the cell
+/// sprites in the new layout
+pub fn update_layout(
+    mut commands: Commands,
+    mut game_state: ResMut<GameState>,
+    textures: Res<Textures>,
+    cells: Query<Entity, CellEntity>,
+) {
+    let hexagonal = game_state.selected_rules.to_rules().neighborhood == Neighborhood::Hexagonal;
+    if hexagonal == game_state.hexagonal {
+        return;
+    }
+    game_state.hexagonal = hexagonal;
+    for entity in &cells {
+        commands.entity(entity).despawn();
+    }
+    spawn_cells(&mut commands, &game_state, &textures);
+}
+
+/// Zooms the camera with the mouse wheel, keeping the point under the cursor fixed
+pub fn zoom_camera(
+    mut game_state: ResMut<GameState>,
+    mut wheel_events: EventReader<MouseWheel>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    mut q_camera: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+) {
+    let scroll: f32 = wheel_events
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / config::PIXELS_PER_SCROLL_LINE,
+        })
+        .sum();
+    if scroll == 0.0 {
+        return;
+    }
+    let Ok(window) = q_windows.get_single() else {
+        return;
+    };
+    let Ok((mut transform, mut projection)) = q_camera.get_single_mut() else {
+        return;
+    };
+
+    game_state.follow_population = false; // The camera is being moved by hand now
+    let old_scale = projection.scale;
+    let new_scale = (old_scale * config::ZOOM_STEP.powf(-scroll)).clamp(config::MIN_ZOOM, config::MAX_ZOOM);
+    projection.scale = new_scale;
+
+    // Offset of the cursor from the window center, in screen pixels with y pointing up
+    if let Some(cursor_pos) = window.cursor_position() {
+        let offset = Vec2::new(
+            cursor_pos.x - window.width() / 2.0,
+            window.height() / 2.0 - cursor_pos.y,
+        );
+        let shift = offset * (old_scale - new_scale);
+        transform.translation += shift.extend(0.0);
+    }
+}
+
+/// Pans the camera by dragging with the middle or right mouse button
+pub fn drag_camera(
+    mut game_state: ResMut<GameState>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut motion_events: EventReader<MouseMotion>,
+    mut q_camera: Query<(&mut Transform, &OrthographicProjection), With<Camera2d>>,
+) {
+    let delta: Vec2 = motion_events.read().map(|event| event.delta).sum();
+    if delta == Vec2::ZERO || !(buttons.pressed(MouseButton::Middle) || buttons.pressed(MouseButton::Right)) {
+        return;
+    }
+    game_state.follow_population = false;
+    if let Ok((mut transform, projection)) = q_camera.get_single_mut() {
+        // Screen y points down while world y points up
+        transform.translation.x -= delta.x * projection.scale;
+        transform.translation.y += delta.y * projection.scale;
+    }
+}
+
+/// Pans the camera with i/j/k/l, and fits the whole grid (or the live cells of the unbounded universe) in the
+/// window with home
+pub fn move_camera(
+    mut game_state: ResMut<GameState>,
+    keys: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    mut q_camera: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+) {
+    let Ok((mut transform, mut projection)) = q_camera.get_single_mut() else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::Home) {
+        if let Ok(window) = q_windows.get_single() {
+            let (size, center) = live_region(&game_state).unwrap_or((game_state.grid_size(), game_state.grid_center()));
+            projection.scale = fit_scale(size, window);
+            transform.translation = center.extend(transform.translation.z);
+        }
+        return;
+    }
+
+    let mut direction = Vec2::ZERO;
+    if keys.pressed(KeyCode::KeyI) {
+        direction.y += 1.0;
+    }
+    if keys.pressed(KeyCode::KeyK) {
+        direction.y -= 1.0;
+    }
+    if keys.pressed(KeyCode::KeyJ) {
+        direction.x -= 1.0;
+    }
+    if keys.pressed(KeyCode::KeyL) {
+        direction.x += 1.0;
+    }
+    if direction != Vec2::ZERO {
+        game_state.follow_population = false;
+    }
+    let pan = direction * config::CAMERA_PAN_SPEED * projection.scale * time.delta_seconds();
+    transform.translation += pan.extend(0.0);
+}
+
+/// Eases the camera towards fitting the live cells of the unbounded universe, so a spreading pattern stays in view
+pub fn follow_population(
+    game_state: Res<GameState>,
+    time: Res<Time>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    mut q_camera: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+) {
+    let (Some((size, center)), Ok(window), Ok((mut transform, mut projection))) = (
+        live_region(&game_state),
+        q_windows.get_single(),
+        q_camera.get_single_mut(),
+    ) else {
+        return;
+    };
+    // Fraction of the remaining distance covered this frame, independent of the frame rate
+    let t = 1.0 - (-config::CAMERA_FOLLOW_RATE * time.delta_seconds()).exp();
+    let target_scale = fit_scale(size * config::CAMERA_FOLLOW_MARGIN, window);
+    projection.scale += (target_scale - projection.scale) * t;
+    transform.translation = transform.translation.lerp(center.extend(transform.translation.z), t);
+}
+
+/// Toggles whether the camera follows the live cells of the unbounded universe
+pub fn toggle_follow_population(mut game_state: ResMut<GameState>, keys: Res<ButtonInput<KeyCode>>) {
+    if keys.just_pressed(KeyCode::KeyT) {
+        game_state.follow_population = !game_state.follow_population;
+    }
+}
+
+pub fn follow_population_enabled(game_state: Res<GameState>) -> bool {
+    game_state.follow_population
+}
+
+/// World size and center of the box around the live cells of the unbounded universe, if it has any
+fn live_region(game_state: &GameState) -> Option<(Vec2, Vec2)> {
+    let (min_x, min_y, max_x, max_y) = game_state.universe.as_ref()?.live_bounds()?;
+    let min = game_state.cell_position(min_x as isize, min_y as isize);
+    let max = game_state.cell_position(max_x as isize, max_y as isize);
+    Some((max - min + game_state.cell_size, (min + max) / 2.0))
+}
+
+/// Camera scale that just fits a world-space size in the window
+fn fit_scale(size: Vec2, window: &Window) -> f32 {
+    (size.x / window.width())
+        .max(size.y / window.height())
+        .clamp(config::MIN_ZOOM, config::MAX_ZOOM)
 }
 
 pub fn update_cells(mut game_state: ResMut<GameState>) {
-    let rules = &game_state.selected_rules.to_rules();
+    game_state.step();
+}
 
-    // Temporary storage for the next state to avoid mutable borrowing conflicts
-    let mut new_next_cells = vec![vec![false; config::GRID_WIDTH]; config::GRID_HEIGHT];
+pub fn simulation_running(game_state: Res<GameState>) -> bool {
+    !game_state.paused
+}
 
-    // First pass: determine the next state for each cell in parallel
-    new_next_cells.par_iter_mut().enumerate().for_each(|(y, row)| {
-        row.iter_mut().enumerate().for_each(|(x, cell)| {
-            let alive_neighbors = count_alive_neighbors(&game_state.cells, x, y);
-            let is_alive = game_state.cells[y][x].is_alive;
+pub fn toggle_paused(mut game_state: ResMut<GameState>, keys: Res<ButtonInput<KeyCode>>) {
+    if keys.just_pressed(KeyCode::Space) {
+        game_state.paused = !game_state.paused;
+    }
+}
 
-            *cell = if is_alive {
-                rules.survival_counts.contains(&alive_neighbors)
-            } else {
-                rules.birth_counts.contains(&alive_neighbors)
-            };
-        });
-    });
+/// Advances a paused simulation by a single generation
+pub fn step_once(mut game_state: ResMut<GameState>, keys: Res<ButtonInput<KeyCode>>) {
+    if game_state.paused && keys.just_pressed(KeyCode::KeyN) {
+        game_state.step();
+    }
+}
 
-    // Second pass: update game_state.next_cells with the calculated next state
-    game_state.next_cells = new_next_cells;
+/// Steps back or forward through earlier generations and edits, pausing so the result stays on screen
+pub fn undo_redo(mut game_state: ResMut<GameState>, keys: Res<ButtonInput<KeyCode>>) {
+    let game_state = &mut *game_state;
+    let changed = if keys.just_pressed(KeyCode::KeyZ) {
+        game_state.history.undo(&mut game_state.grid)
+    } else if keys.just_pressed(KeyCode::KeyY) {
+        game_state.history.redo(&mut game_state.grid)
+    } else {
+        false
+    };
+    if changed {
+        game_state.paused = true;
+    }
+}
 
-    // Third pass: apply the next state and update activation counts in parallel
-    let next_cells = game_state.next_cells.clone();
-    game_state.cells.par_iter_mut().enumerate().for_each(|(y, row)| {
-        row.iter_mut().enumerate().for_each(|(x, cell)| {
-            let next_alive = next_cells[y][x];
-            if next_alive && !cell.is_alive {
-                cell.activation_count += 1; // Increment count if cell becomes alive
-            }
-            cell.is_alive = next_alive;
-        });
-    });
+pub fn fast_forward_enabled(game_state: Res<GameState>) -> bool {
+    game_state.fast_forward
 }
 
-pub fn render_cells(game_state: Res<GameState>, mut query: Query<&mut Sprite>) {
-    if game_state.mode == Mode::Color {
-        let mut sprites: Vec<_> = query.iter_mut().collect();
-        sprites.par_iter_mut().enumerate().for_each(|(i, sprite)| {
-            let x = i % config::GRID_WIDTH;
-            let y = i / config::GRID_WIDTH;
-            sprite.color = if game_state.cells[y][x].is_alive {
-                config::ALIVE_COLOR
-            } else {
-                config::DEAD_COLOR
-            };
-        });
+/// Steps as many generations as fit in the frame budget, always at least one
+pub fn fast_forward(mut game_state: ResMut<GameState>) {
+    let start = Instant::now();
+    loop {
+        game_state.step();
+        if start.elapsed().as_secs_f32() >= config::FAST_FORWARD_FRAME_BUDGET {
+            break;
+        }
     }
 }
 
-fn count_alive_neighbors(cells: &[Vec<Cell>], x: usize, y: usize) -> usize {
-    let mut count = 0;
-    for dy in -1..=1 {
-        for dx in -1..=1 {
-            if dx == 0 && dy == 0 {
-                continue;
-            }
-            let nx = (x as isize + dx).rem_euclid(config::GRID_WIDTH as isize) as usize;
-            let ny = (y as isize + dy).rem_euclid(config::GRID_HEIGHT as isize) as usize;
-            if cells[ny][nx].is_alive {
-                count += 1;
-            }
+/// Doubles or halves the fixed timestep rate, or toggles fast-forward mode
+pub fn change_speed(mut game_state: ResMut<GameState>, mut time: ResMut<Time<Fixed>>, keys: Res<ButtonInput<KeyCode>>) {
+    if keys.just_pressed(KeyCode::Equal) || keys.just_pressed(KeyCode::NumpadAdd) {
+        game_state.ticks_per_second = (game_state.ticks_per_second * 2.0).min(config::MAX_TICKS_PER_SECOND);
+    } else if keys.just_pressed(KeyCode::Minus) || keys.just_pressed(KeyCode::NumpadSubtract) {
+        game_state.ticks_per_second = (game_state.ticks_per_second / 2.0).max(config::MIN_TICKS_PER_SECOND);
+    } else if keys.just_pressed(KeyCode::KeyM) {
+        game_state.fast_forward = !game_state.fast_forward;
+        return;
+    } else {
+        return;
+    }
+    time.set_timestep_hz(game_state.ticks_per_second);
+}
+
+pub fn universe_enabled(game_state: Res<GameState>) -> bool {
+    game_state.universe.is_some()
+}
+
+pub fn hashlife_enabled(game_state: Res<GameState>) -> bool {
+    matches!(game_state.universe, Some(Universe::HashLife(_)))
+}
+
+/// Doubles or halves the number of generations each HashLife tick jumps
+pub fn change_step_exponent(mut game_state: ResMut<GameState>, keys: Res<ButtonInput<KeyCode>>) {
+    if keys.just_pressed(KeyCode::BracketRight) {
+        game_state.step_exponent = (game_state.step_exponent + 1).min(config::MAX_STEP_EXPONENT);
+    } else if keys.just_pressed(KeyCode::BracketLeft) {
+        game_state.step_exponent = game_state.step_exponent.saturating_sub(1);
+    }
+}
+
+/// Writes every cell's color into the grid texture, one pixel per cell
+pub fn render_cells(game_state: Res<GameState>, textures: Res<Textures>, mut images: ResMut<Assets<Image>>) {
+    if game_state.mode == Mode::Color && !game_state.hexagonal {
+        let Some(image) = images.get_mut(&textures.grid_texture) else {
+            return;
+        };
+        let width = game_state.grid.width();
+        let height = game_state.grid.height();
+        if image.size() != UVec2::new(width as u32, height as u32) {
+            return; // The grid was just resized and the new texture isn't in place yet
         }
+
+        let colors: Vec<[u8; 4]> = state_colors(&game_state.selected_rules.to_rules())
+            .iter()
+            .map(|color| color.to_srgba().to_u8_array())
+            .collect();
+        image
+            .data
+            .par_chunks_mut(width * 4)
+            .enumerate()
+            .for_each(|(row, pixels)| {
+                // Image rows run top to bottom while grid rows run bottom to top
+                let y = height - 1 - row;
+                for (x, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+                    // Dying cells left over from rules with more states are drawn as the last dying state
+                    let state = (game_state.grid.state(x, y) as usize).min(colors.len() - 1);
+                    pixel.copy_from_slice(&colors[state]);
+                }
+            });
     }
-    count
 }
 
-pub fn render_images(game_state: Res<GameState>, textures: Res<Textures>, mut query: Query<&mut Handle<Image>>) {
+/// Writes every cell's color into the corners of its hexagon in the hexagon mesh
+pub fn render_hex_cells(game_state: Res<GameState>, textures: Res<Textures>, mut meshes: ResMut<Assets<Mesh>>) {
+    if game_state.mode != Mode::Color || !game_state.hexagonal {
+        return;
+    }
+    let Some(mesh) = meshes.get_mut(&textures.hex_grid_mesh) else {
+        return;
+    };
+    let width = game_state.grid.width();
+    if mesh.count_vertices() != width * game_state.grid.height() * 6 {
+        *mesh = hex_grid_mesh(&game_state); // The grid was resized since the mesh was built
+    }
+    let Some(VertexAttributeValues::Float32x4(vertex_colors)) = mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR) else {
+        return;
+    };
+
+    let colors: Vec<[f32; 4]> = state_colors(&game_state.selected_rules.to_rules())
+        .iter()
+        .map(|color| color.to_linear().to_f32_array())
+        .collect();
+    vertex_colors
+        .par_chunks_mut(width * 6)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for (x, corners) in row.chunks_exact_mut(6).enumerate() {
+                let state = (game_state.grid.state(x, y) as usize).min(colors.len() - 1);
+                corners.fill(colors[state]);
+            }
+        });
+}
+
+pub fn render_images(
+    game_state: Res<GameState>,
+    textures: Res<Textures>,
+    mut query: Query<(&CellSprite, &mut Handle<Image>, &mut Sprite)>,
+) {
     if game_state.mode == Mode::Image {
-        for (i, mut texture_handle) in query.iter_mut().enumerate() {
-            let x = i % config::GRID_WIDTH;
-            let y = i / config::GRID_WIDTH;
-
-            // Set the texture based on the cell state
-            *texture_handle = if game_state.cells[y][x].is_alive {
-                textures.alive_texture.clone()
-            } else {
-                textures.dead_texture.clone()
+        let colors = state_colors(&game_state.selected_rules.to_rules());
+        for (cell_sprite, mut texture_handle, mut sprite) in query.iter_mut() {
+            // Set the texture based on the cell state, tinting dying cells with their place on the color ramp
+            let state = game_state.grid.state(cell_sprite.x, cell_sprite.y) as usize;
+            let (texture, tint) = match state {
+                0 => (&textures.dead_texture, Color::WHITE),
+                1 => (&textures.alive_texture, Color::WHITE),
+                _ => (&textures.alive_texture, colors[state.min(colors.len() - 1)]),
             };
+            if *texture_handle != *texture {
+                *texture_handle = texture.clone();
+            }
+            if sprite.color != tint {
+                sprite.color = tint;
+            }
         }
     }
 }
 
+/// Color of each cell state: dead, alive, then a ramp from the dying color towards dead for Generations rules.
+/// Wireworld has a color of its own for each state instead.
+fn state_colors(rules: &Rules) -> Vec<Color> {
+    if rules.wireworld {
+        return vec![
+            config::DEAD_COLOR,
+            config::ELECTRON_HEAD_COLOR,
+            config::ELECTRON_TAIL_COLOR,
+            config::CONDUCTOR_COLOR,
+        ];
+    }
+    let states = rules.states;
+    let dying = (2..states).map(|state| {
+        // The last dying state stops one step short of the dead color, so it stays visible
+        let fade = (state - 2) as f32 / (states - 2) as f32;
+        config::DYING_COLOR.mix(&config::DEAD_COLOR, fade)
+    });
+    [config::DEAD_COLOR, config::ALIVE_COLOR]
+        .into_iter()
+        .chain(dying)
+        .collect()
+}
+
+/// Draws the part of the unbounded universe the camera can see into the universe texture: one texel per cell, or
+/// per block of cells when zoomed out so far that several cells share a pixel
+pub fn render_universe(
+    game_state: Res<GameState>,
+    textures: Res<Textures>,
+    mut images: ResMut<Assets<Image>>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    q_camera: Query<(&GlobalTransform, &OrthographicProjection), With<Camera2d>>,
+    mut q_sprite: Query<(&mut Transform, &mut Sprite), With<UniverseSprite>>,
+) {
+    let Some(universe) = &game_state.universe else {
+        return;
+    };
+    let (Ok(window), Ok((camera_transform, projection)), Ok((mut transform, mut sprite))) =
+        (q_windows.get_single(), q_camera.get_single(), q_sprite.get_single_mut())
+    else {
+        return;
+    };
+
+    // Smallest power-of-two block of cells that covers at least a whole pixel
+    let cells_per_pixel = projection.scale / game_state.cell_size;
+    let scale_log2 = cells_per_pixel.log2().ceil().max(0.0) as u32;
+
+    // Visible cells, widened to whole blocks
+    let half_view = Vec2::new(window.width(), window.height()) * projection.scale / 2.0;
+    let camera_center = camera_transform.translation().truncate();
+    let (min_x, min_y) = game_state.world_to_cell(camera_center - half_view);
+    let (max_x, max_y) = game_state.world_to_cell(camera_center + half_view);
+    let (x, y) = (
+        (min_x as i64 >> scale_log2) << scale_log2,
+        (min_y as i64 >> scale_log2) << scale_log2,
+    );
+    let columns = ((max_x as i64 - x) >> scale_log2) as usize + 1;
+    let rows = ((max_y as i64 - y) >> scale_log2) as usize + 1;
+
+    if !images
+        .get(&textures.universe_texture)
+        .is_some_and(|image| image.size() == UVec2::new(columns as u32, rows as u32))
+    {
+        images.insert(&textures.universe_texture, grid_image(columns, rows));
+    }
+    let Some(image) = images.get_mut(&textures.universe_texture) else {
+        return;
+    };
+    let alive = config::ALIVE_COLOR.to_srgba().to_u8_array();
+    let dead = config::DEAD_COLOR.to_srgba().to_u8_array();
+    for pixel in image.data.chunks_exact_mut(4) {
+        pixel.copy_from_slice(&dead);
+    }
+    universe.for_each_live_block(x, y, columns, rows, scale_log2, |column, row| {
+        // Image rows run top to bottom while universe rows run bottom to top
+        let i = ((rows - 1 - row) * columns + column) * 4;
+        image.data[i..i + 4].copy_from_slice(&alive);
+    });
+
+    let block_size = (1u64 << scale_log2) as f32 * game_state.cell_size;
+    sprite.custom_size = Some(Vec2::new(columns as f32, rows as f32) * block_size);
+    let corner = game_state.cell_position(x as isize, y as isize) - game_state.cell_size / 2.0;
+    transform.translation = corner.extend(0.0);
+}
+
+/// World position under the cursor, converted through the camera's pan and zoom
+fn cursor_world_position(
+    q_windows: &Query<&Window, With<PrimaryWindow>>,
+    q_camera: &Query<(&Camera, &GlobalTransform)>,
+) -> Option<Vec2> {
+    let cursor_pos = q_windows.get_single().ok()?.cursor_position()?;
+    let (camera, camera_transform) = q_camera.get_single().ok()?;
+    camera.viewport_to_world_2d(camera_transform, cursor_pos)
+}
+
 pub fn trigger_selected_pattern(
     mut game_state: ResMut<GameState>,
     buttons: Res<ButtonInput<MouseButton>>,
     q_windows: Query<&Window, With<PrimaryWindow>>,
+    q_camera: Query<(&Camera, &GlobalTransform)>,
 ) {
-    if buttons.just_pressed(MouseButton::Left) {
-        if let Ok(window) = q_windows.get_single() {
-            if let Some(cursor_pos) = window.cursor_position() {
-                // Calculate the grid offset to position the grid center at the window center
-                let grid_offset_x = (config::GRID_WIDTH as f32 * config::CELL_SIZE) / 2.0;
-                let grid_offset_y = (config::GRID_HEIGHT as f32 * config::CELL_SIZE) / 2.0;
+    // With a brush chosen, the left button paints cells instead
+    if buttons.just_pressed(MouseButton::Left) && game_state.brush.is_none() {
+        if let Some(world_pos) = cursor_world_position(&q_windows, &q_camera) {
+            if let Some((cell_x, cell_y)) = game_state.placement_cell(world_pos) {
+                // Add the selected pattern at the clicked position
+                let game_state = &mut *game_state;
+                let selected_pattern = game_state.selected_pattern;
+                let clipped = match &mut game_state.universe {
+                    Some(universe) => selected_pattern.add_to_grid(
+                        universe,
+                        &game_state.loaded_patterns,
+                        game_state.pattern_orientation,
+                        cell_x,
+                        cell_y,
+                    ),
+                    None => {
+                        game_state.history.record(&game_state.grid);
+                        selected_pattern.add_to_grid(
+                            &mut game_state.grid,
+                            &game_state.loaded_patterns,
+                            game_state.pattern_orientation,
+                            cell_x,
+                            cell_y,
+                        )
+                    }
+                };
+                if clipped {
+                    info!(
+                        "{} was clipped at the edge of the grid",
+                        game_state.selected_pattern_name()
+                    );
+                }
 
-                // Adjust cursor position relative to the grid center
-                let adjusted_x = cursor_pos.x - window.width() / 2.0 + grid_offset_x;
-                let adjusted_y = (window.height() - cursor_pos.y) - window.height() / 2.0 + grid_offset_y;
+                // Circuits need Wireworld, and loaded patterns optionally adopt the rules they declare
+                let pattern_rules = match selected_pattern {
+                    Pattern::Loaded(index) if game_state.use_pattern_rules => {
+                        game_state.loaded_patterns[index].rules.clone()
+                    }
+                    pattern => pattern.rules(),
+                };
+                if let Some(rules) = pattern_rules {
+                    if let Err(err) = game_state.set_rules(SelectedRules::from(rules)) {
+                        warn!("Kept the current rules: {}", err);
+                    }
+                }
+            }
+        }
+    }
+}
 
-                // Convert to grid coordinates
-                let grid_x = (adjusted_x / config::CELL_SIZE) as isize;
-                let grid_y = (adjusted_y / config::CELL_SIZE) as isize;
+/// Shows translucent sprites over exactly the cells a click would set, following the cursor
+pub fn update_pattern_preview(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    textures: Res<Textures>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    q_camera: Query<(&Camera, &GlobalTransform)>,
+    ghosts: Query<Entity, With<GhostCell>>,
+    mut last_preview: Local<Option<PatternPreview>>,
+) {
+    let cell = cursor_world_position(&q_windows, &q_camera).and_then(|world_pos| game_state.placement_cell(world_pos));
+    // The brush paints one cell at a time
+    let (pattern, orientation) = match game_state.brush {
+        Some(_) => (Pattern::Single, Orientation::default()),
+        None => (game_state.selected_pattern, game_state.pattern_orientation),
+    };
+    let preview = cell.map(|(x, y)| PatternPreview {
+        x,
+        y,
+        pattern,
+        orientation,
+        grid_size: (game_state.grid.width(), game_state.grid.height()),
+        hexagonal: game_state.hexagonal,
+    });
+    if preview == *last_preview {
+        return; // Nothing moved, so the existing ghost sprites are still right
+    }
+    *last_preview = preview;
 
-                // Clamp grid coordinates to be within bounds
-                let grid_x = grid_x.clamp(0, config::GRID_WIDTH as isize - 1);
-                let grid_y = grid_y.clamp(0, config::GRID_HEIGHT as isize - 1);
+    for entity in &ghosts {
+        commands.entity(entity).despawn();
+    }
+    let Some(preview) = preview else {
+        return;
+    };
 
-                // Add the blinker pattern at the clamped grid position
-                let selected_pattern = game_state.selected_pattern;
-                selected_pattern.add_to_grid(&mut game_state.cells, grid_x as usize, grid_y as usize);
-            }
+    let cells = preview
+        .orientation
+        .apply(preview.pattern.cells(&game_state.loaded_patterns));
+    for (dx, dy) in cells {
+        let (x, y) = (preview.x + dx as isize, preview.y + dy as isize);
+        // The unbounded universe has no edges to wrap around
+        let cell = match game_state.universe {
+            Some(_) => Some((x, y)),
+            None => game_state.grid.wrap(x, y).map(|(x, y)| (x as isize, y as isize)),
+        };
+        let Some((cell_x, cell_y)) = cell else {
+            continue;
+        };
+        let transform = Transform::from_translation(game_state.cell_position(cell_x, cell_y).extend(1.0));
+        if game_state.hexagonal {
+            commands.spawn((
+                MaterialMesh2dBundle {
+                    mesh: textures.hex_cell_mesh.clone().into(),
+                    material: textures.ghost_material.clone(),
+                    transform,
+                    ..Default::default()
+                },
+                GhostCell,
+            ));
+        } else {
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: config::GHOST_COLOR,
+                        custom_size: Some(Vec2::splat(game_state.cell_size)),
+                        ..Default::default()
+                    },
+                    transform,
+                    ..Default::default()
+                },
+                GhostCell,
+            ));
         }
     }
 }
 
+/// Rotates the selected pattern a quarter turn clockwise, or mirrors it
+pub fn orient_selected_pattern(mut game_state: ResMut<GameState>, keys: Res<ButtonInput<KeyCode>>) {
+    if keys.just_pressed(KeyCode::KeyR) {
+        game_state.pattern_orientation = game_state.pattern_orientation.rotated();
+    } else if keys.just_pressed(KeyCode::KeyF) {
+        game_state.pattern_orientation = game_state.pattern_orientation.flipped();
+    }
+}
+
+/// Cycles through the patterns, putting the brush away so clicks place them again
 pub fn change_selected_pattern(mut game_state: ResMut<GameState>, keys: Res<ButtonInput<KeyCode>>) {
+    let loaded = game_state.loaded_patterns.len();
     if keys.just_pressed(KeyCode::ArrowRight) || keys.just_pressed(KeyCode::KeyD) {
-        game_state.selected_pattern = game_state.selected_pattern.next();
+        game_state.selected_pattern = game_state.selected_pattern.next(loaded);
+        game_state.brush = None;
     } else if keys.just_pressed(KeyCode::ArrowLeft) || keys.just_pressed(KeyCode::KeyA) {
-        game_state.selected_pattern = game_state.selected_pattern.previous();
+        game_state.selected_pattern = game_state.selected_pattern.previous(loaded);
+        game_state.brush = None;
+    }
+}
+
+/// Picks the state painted with the mouse with 0-3, ignoring states the selected rules don't have. Pressing the
+/// brush's own key again puts it away.
+pub fn select_brush(mut game_state: ResMut<GameState>, keys: Res<ButtonInput<KeyCode>>) {
+    let digits = [KeyCode::Digit0, KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3];
+    let Some(state) = digits.iter().position(|&key| keys.just_pressed(key)) else {
+        return;
+    };
+    let state = state as u8;
+    if state as usize >= game_state.selected_rules.to_rules().states {
+        return;
+    }
+    game_state.brush = (game_state.brush != Some(state)).then_some(state);
+}
+
+/// Paints the brush state into every cell the cursor passes over while the left button is held, filling in the
+/// cells between where it was on the last frame and where it is now
+pub fn paint_cells(
+    mut game_state: ResMut<GameState>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    q_camera: Query<(&Camera, &GlobalTransform)>,
+    mut last_cell: Local<Option<(isize, isize)>>,
+) {
+    let brush = match game_state.brush {
+        Some(brush) if buttons.pressed(MouseButton::Left) => brush,
+        _ => {
+            *last_cell = None;
+            return;
+        }
+    };
+    // The rules may have changed to ones without the brush's state since it was picked
+    if brush as usize >= game_state.selected_rules.to_rules().states {
+        return;
     }
+    let Some(cell) = cursor_world_position(&q_windows, &q_camera).map(|world_pos| game_state.world_to_cell(world_pos))
+    else {
+        return;
+    };
+    if *last_cell == Some(cell) {
+        return;
+    }
+
+    let game_state = &mut *game_state;
+    if buttons.just_pressed(MouseButton::Left) && game_state.universe.is_none() {
+        game_state.history.record(&game_state.grid); // A whole stroke is undone at once
+    }
+    let (from_x, from_y) = last_cell.unwrap_or(cell);
+    let (dx, dy) = (cell.0 - from_x, cell.1 - from_y);
+    let steps = dx.abs().max(dy.abs()).max(1);
+    for i in 0..=steps {
+        let fraction = i as f32 / steps as f32;
+        let x = from_x + (dx as f32 * fraction).round() as isize;
+        let y = from_y + (dy as f32 * fraction).round() as isize;
+        match &mut game_state.universe {
+            Some(universe) => universe.set_alive(x as i64, y as i64, brush == 1),
+            None => {
+                let grid = &mut game_state.grid;
+                if (0..grid.width() as isize).contains(&x) && (0..grid.height() as isize).contains(&y) {
+                    grid.set_state(x as usize, y as usize, brush);
+                }
+            }
+        }
+    }
+    *last_cell = Some(cell);
 }
 
+/// Cycles through the presets, skipping any the unbounded universe can't run
 pub fn change_selected_rules(mut game_state: ResMut<GameState>, keys: Res<ButtonInput<KeyCode>>) {
-    if keys.just_pressed(KeyCode::ArrowUp) || keys.just_pressed(KeyCode::KeyW) {
-        game_state.selected_rules = game_state.selected_rules.next();
+    let cycle = if keys.just_pressed(KeyCode::ArrowUp) || keys.just_pressed(KeyCode::KeyW) {
+        SelectedRules::next
     } else if keys.just_pressed(KeyCode::ArrowDown) || keys.just_pressed(KeyCode::KeyS) {
-        game_state.selected_rules = game_state.selected_rules.previous();
+        SelectedRules::previous
+    } else {
+        return;
+    };
+    let mut selected_rules = cycle(&game_state.selected_rules);
+    // Conway is always supported, so this stops within one lap
+    while game_state.set_rules(selected_rules.clone()).is_err() {
+        selected_rules = cycle(&selected_rules);
     }
 }
 
@@ -230,22 +967,164 @@ pub fn update_selected_pattern_text(
     mut query: Query<&mut Text, With<SelectedPatternText>>,
 ) {
     if let Ok(mut text) = query.get_single_mut() {
-        text.sections[0].value = format!("Selected Pattern: {:?}", game_state.selected_pattern);
+        text.sections[0].value = match game_state.brush {
+            Some(state) => format!("Brush: {}", game_state.state_name(state)),
+            None => format!("Selected Pattern: {}", game_state.selected_pattern_description()),
+        };
     }
 }
 
 pub fn update_selected_rules_text(game_state: Res<GameState>, mut query: Query<&mut Text, With<SelectedRulesText>>) {
     if let Ok(mut text) = query.get_single_mut() {
-        text.sections[0].value = format!("Rules: {:?}", game_state.selected_rules);
+        text.sections[0].value = format!("Rules: {}", game_state.selected_rules);
+    }
+}
+
+/// Loads RLE files dropped onto the window and selects the newest one
+pub fn load_dropped_patterns(mut game_state: ResMut<GameState>, mut drop_events: EventReader<FileDragAndDrop>) {
+    for event in drop_events.read() {
+        if let FileDragAndDrop::DroppedFile { path_buf, .. } = event {
+            match game_of_life_bevy::rle::read_file(path_buf) {
+                Ok(pattern) => {
+                    game_state.loaded_patterns.push(pattern);
+                    game_state.selected_pattern = Pattern::Loaded(game_state.loaded_patterns.len() - 1);
+                }
+                Err(err) => warn!("{}", err),
+            }
+        }
+    }
+}
+
+pub fn update_paused_text(game_state: Res<GameState>, mut query: Query<&mut Text, With<PausedText>>) {
+    if let Ok(mut text) = query.get_single_mut() {
+        text.sections[0].value = if game_state.paused {
+            "Paused (space to resume, n to step, z/y to rewind/replay)".to_string()
+        } else {
+            String::new()
+        };
+    }
+}
+
+pub fn update_speed_text(
+    game_state: Res<GameState>,
+    time: Res<Time>,
+    mut tick_counter: ResMut<TickCounter>,
+    mut query: Query<&mut Text, With<SpeedText>>,
+) {
+    tick_counter.elapsed += time.delta_seconds();
+    if tick_counter.elapsed >= config::TICK_COUNTER_INTERVAL {
+        let generations = game_state.generation - tick_counter.last_generation;
+        tick_counter.achieved_ticks_per_second = generations as f64 / tick_counter.elapsed as f64;
+        tick_counter.last_generation = game_state.generation;
+        tick_counter.elapsed = 0.0;
+    }
+
+    if let Ok(mut text) = query.get_single_mut() {
+        let target = if game_state.fast_forward {
+            "max".to_string()
+        } else {
+            format!("{}", game_state.ticks_per_second)
+        };
+        text.sections[0].value = if matches!(game_state.universe, Some(Universe::HashLife(_))) {
+            // Each tick jumps several generations, so the measured rate counts generations rather than ticks
+            format!(
+                "TPS: {} target, {:.1} generations/s, step 2^{}",
+                target, tick_counter.achieved_ticks_per_second, game_state.step_exponent
+            )
+        } else {
+            format!(
+                "TPS: {} target, {:.1} actual",
+                target, tick_counter.achieved_ticks_per_second
+            )
+        };
+    }
+}
+
+pub fn rule_entry_inactive(rule_entry: Res<RuleEntry>) -> bool {
+    !rule_entry.active
+}
+
+pub fn edit_rule_entry(
+    mut game_state: ResMut<GameState>,
+    mut rule_entry: ResMut<RuleEntry>,
+    mut key_events: EventReader<KeyboardInput>,
+) {
+    for event in key_events.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+
+        // Enter opens the entry box; every other key is left to the regular shortcuts
+        if !rule_entry.active {
+            if event.key_code == KeyCode::Enter {
+                rule_entry.active = true;
+                rule_entry.buffer.clear();
+                rule_entry.error = None;
+            }
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Enter => match rule_entry
+                .buffer
+                .parse()
+                .map_err(|err: ParseRulesError| err.to_string())
+                .and_then(|selected_rules| game_state.set_rules(selected_rules))
+            {
+                Ok(()) => rule_entry.active = false,
+                Err(err) => rule_entry.error = Some(err),
+            },
+            Key::Escape => rule_entry.active = false,
+            Key::Backspace => {
+                rule_entry.buffer.pop();
+            }
+            Key::Character(c) => rule_entry.buffer.push_str(c),
+            _ => {}
+        }
+    }
+}
+
+pub fn update_rule_entry_text(rule_entry: Res<RuleEntry>, mut query: Query<&mut Text, With<RuleEntryText>>) {
+    if let Ok(mut text) = query.get_single_mut() {
+        text.sections[0].value = if !rule_entry.active {
+            String::new()
+        } else if let Some(error) = &rule_entry.error {
+            format!("New rules: {}_\n{}", rule_entry.buffer, error)
+        } else {
+            format!("New rules: {}_", rule_entry.buffer)
+        };
+    }
+}
+
+pub fn export_grid(game_state: Res<GameState>, keys: Res<ButtonInput<KeyCode>>) {
+    if keys.just_pressed(KeyCode::KeyE) {
+        let rules = game_state.selected_rules.to_rules();
+        let universe_grid = match &game_state.universe {
+            Some(universe) => match universe.to_grid() {
+                Some(grid) => Some(grid),
+                None => {
+                    warn!("Could not save: the live cells are spread too far apart");
+                    return;
+                }
+            },
+            None => None,
+        };
+        let grid = universe_grid.as_ref().unwrap_or(&game_state.grid);
+        match game_of_life_bevy::export::write_file(&game_state.export_path, grid, &rules) {
+            Ok(()) => info!("Saved grid to {}", game_state.export_path.display()),
+            Err(err) => warn!("Could not save grid to {}: {}", game_state.export_path.display(), err),
+        }
     }
 }
 
 pub fn kill_all_cells(mut game_state: ResMut<GameState>, keys: Res<ButtonInput<KeyCode>>) {
     if keys.just_pressed(KeyCode::Escape) {
-        for y in 0..config::GRID_HEIGHT {
-            for x in 0..config::GRID_WIDTH {
-                game_state.cells[y][x].is_alive = false;
-            }
+        let game_state = &mut *game_state;
+        if let Some(universe) = &mut game_state.universe {
+            universe.clear();
+            return;
         }
+        game_state.history.record(&game_state.grid);
+        game_state.grid.clear();
     }
 }
@@ -0,0 +1,128 @@
+use clap::ValueEnum;
+
+/// How the edges of the grid are joined together
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, ValueEnum)]
+pub enum Topology {
+    /// Opposite edges are joined, so cells leaving one side come back on the other
+    #[default]
+    Torus,
+    /// Everything beyond the edges is permanently dead
+    Bounded,
+    /// Left and right edges are joined; top and bottom are joined with a twist (mirrored horizontally)
+    KleinBottle,
+    /// Both pairs of opposite edges are joined with a twist (the real projective plane)
+    CrossSurface,
+    /// The left edge is joined to the bottom edge and the right edge to the top; needs a square grid
+    Sphere,
+}
+
+impl Topology {
+    /// Maps a position that may lie outside a `width` x `height` grid back onto it,
+    /// or returns `None` if it falls off the edge of a bounded grid
+    pub fn wrap(self, x: isize, y: isize, width: usize, height: usize) -> Option<(usize, usize)> {
+        let (w, h) = (width as isize, height as isize);
+        if (0..w).contains(&x) && (0..h).contains(&y) {
+            return Some((x as usize, y as usize));
+        }
+
+        match self {
+            Topology::Torus => Some((x.rem_euclid(w) as usize, y.rem_euclid(h) as usize)),
+            Topology::Bounded => None,
+            Topology::KleinBottle => {
+                // Each trip across the top or bottom edge mirrors the column
+                let flip_x = y.div_euclid(h) % 2 != 0;
+                let x = x.rem_euclid(w);
+                let x = if flip_x { w - 1 - x } else { x };
+                Some((x as usize, y.rem_euclid(h) as usize))
+            }
+            Topology::CrossSurface => {
+                let flip_x = y.div_euclid(h) % 2 != 0;
+                let flip_y = x.div_euclid(w) % 2 != 0;
+                let (x, y) = (x.rem_euclid(w), y.rem_euclid(h));
+                let x = if flip_x { w - 1 - x } else { x };
+                let y = if flip_y { h - 1 - y } else { y };
+                Some((x as usize, y as usize))
+            }
+            Topology::Sphere => {
+                // Crossing an edge swaps the roles of x and y; a few hops bring any nearby position back
+                let (mut x, mut y) = (x, y);
+                for _ in 0..8 {
+                    (x, y) = if x < 0 {
+                        (y, -1 - x)
+                    } else if x >= w {
+                        (y, h - 1 + w - x)
+                    } else if y < 0 {
+                        (-1 - y, x)
+                    } else if y >= h {
+                        (w - 1 + h - y, x)
+                    } else {
+                        return Some((x as usize, y as usize));
+                    };
+                }
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_across_each_edge() {
+        let cases = [
+            (Topology::Torus, (-1, 0), Some((3, 0))),
+            (Topology::Torus, (4, 4), Some((0, 0))),
+            (Topology::Bounded, (2, 3), Some((2, 3))),
+            (Topology::Bounded, (-1, 0), None),
+            (Topology::Bounded, (0, 4), None),
+            (Topology::KleinBottle, (-1, 1), Some((3, 1))),
+            (Topology::KleinBottle, (1, 4), Some((2, 0))),
+            (Topology::KleinBottle, (1, -1), Some((2, 3))),
+            (Topology::CrossSurface, (1, 4), Some((2, 0))),
+            (Topology::CrossSurface, (4, 1), Some((0, 2))),
+            (Topology::Sphere, (-1, 2), Some((2, 0))),
+            (Topology::Sphere, (4, 1), Some((1, 3))),
+            (Topology::Sphere, (2, -1), Some((0, 2))),
+            (Topology::Sphere, (1, 4), Some((3, 1))),
+        ];
+        for (topology, (x, y), wrapped) in cases {
+            assert_eq!(topology.wrap(x, y, 4, 4), wrapped, "({x}, {y}) on {topology:?}");
+        }
+    }
+
+    #[test]
+    fn neighbors_are_mutual() {
+        // Whenever one cell counts another as a neighbor across an edge, the other counts it back
+        let size = 6;
+        let offsets: Vec<(isize, isize)> = (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+            .filter(|&offset| offset != (0, 0))
+            .collect();
+        let neighbors = |topology: Topology, x: isize, y: isize| -> Vec<(usize, usize)> {
+            offsets
+                .iter()
+                .filter_map(|(dx, dy)| topology.wrap(x + dx, y + dy, size, size))
+                .collect()
+        };
+        for topology in [
+            Topology::Torus,
+            Topology::Bounded,
+            Topology::KleinBottle,
+            Topology::CrossSurface,
+            Topology::Sphere,
+        ] {
+            for y in 0..size as isize {
+                for x in 0..size as isize {
+                    for (nx, ny) in neighbors(topology, x, y) {
+                        assert!(
+                            neighbors(topology, nx as isize, ny as isize).contains(&(x as usize, y as usize)),
+                            "({x}, {y}) and ({nx}, {ny}) on {topology:?}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::grid::Grid;
+use crate::hashlife::HashLife;
+use crate::patterns::Board;
+use crate::rules::{Neighborhood, Rules};
+use crate::sparse::SparseUniverse;
+use crate::topology::Topology;
+
+/// Most cells `Universe::to_grid` will copy out, 128 MiB once bit-packed
+const MAX_GRID_CELLS: usize = 1 << 30;
+
+/// An unbounded universe, backed by whichever engine was chosen
+pub enum Universe {
+    /// Quadtree stepped with HashLife, fastest on repetitive patterns and large jumps
+    HashLife(HashLife),
+    /// Map of 64 x 64 chunks stepped one generation at a time, fastest on chaotic patterns
+    Sparse(SparseUniverse),
+}
+
+impl Universe {
+    /// Whether the rules have only live and dead cells and the 3x3 neighborhood, and can be run without bringing
+    /// the empty background to life. Hexagonal rules are left out too, since the universe is only drawn as square
+    /// cells.
+    pub fn supports(rules: &Rules) -> bool {
+        HashLife::supports(rules) && rules.neighborhood != Neighborhood::Hexagonal
+    }
+
+    pub fn is_alive(&self, x: i64, y: i64) -> bool {
+        match self {
+            Universe::HashLife(hashlife) => hashlife.is_alive(x, y),
+            Universe::Sparse(sparse) => sparse.is_alive(x, y),
+        }
+    }
+
+    pub fn set_alive(&mut self, x: i64, y: i64, is_alive: bool) {
+        match self {
+            Universe::HashLife(hashlife) => hashlife.set_alive(x, y, is_alive),
+            Universe::Sparse(sparse) => sparse.set_alive(x, y, is_alive),
+        }
+    }
+
+    /// Number of live cells
+    pub fn population(&self) -> u64 {
+        match self {
+            Universe::HashLife(hashlife) => hashlife.population(),
+            Universe::Sparse(sparse) => sparse.population(),
+        }
+    }
+
+    /// Makes every cell dead
+    pub fn clear(&mut self) {
+        match self {
+            Universe::HashLife(hashlife) => hashlife.clear(),
+            Universe::Sparse(sparse) => sparse.clear(),
+        }
+    }
+
+    /// Advances the universe and returns how many generations passed: 2^`step_log2` for HashLife, and always
+    /// one for the sparse universe, which has no way to skip ahead.
+    ///
+    /// Panics if the rules contain B0, dying states or a larger neighborhood; check them with `Universe::supports`
+    /// first.
+    pub fn step(&mut self, rules: &Rules, step_log2: u32) -> u64 {
+        match self {
+            Universe::HashLife(hashlife) => {
+                hashlife.step(rules, step_log2);
+                1 << step_log2
+            }
+            Universe::Sparse(sparse) => {
+                sparse.step(rules);
+                1
+            }
+        }
+    }
+
+    /// Calls `f(column, row)` for every block of 2^`scale_log2` x 2^`scale_log2` cells containing a live cell,
+    /// within the `columns` x `rows` blocks whose bottom-left cell is `(x, y)`. `x` and `y` must be multiples of
+    /// the block size. A block may be reported more than once.
+    pub fn for_each_live_block(
+        &self,
+        x: i64,
+        y: i64,
+        columns: usize,
+        rows: usize,
+        scale_log2: u32,
+        f: impl FnMut(usize, usize),
+    ) {
+        match self {
+            Universe::HashLife(hashlife) => hashlife.for_each_live_block(x, y, columns, rows, scale_log2, f),
+            Universe::Sparse(sparse) => sparse.for_each_live_block(x, y, columns, rows, scale_log2, f),
+        }
+    }
+
+    /// Smallest and largest coordinates of live cells, as `(min_x, min_y, max_x, max_y)`
+    pub fn live_bounds(&self) -> Option<(i64, i64, i64, i64)> {
+        match self {
+            Universe::HashLife(hashlife) => hashlife.live_bounds(),
+            Universe::Sparse(sparse) => sparse.live_bounds(),
+        }
+    }
+
+    /// Copies the live region into a bounded grid just large enough to hold it, e.g. for exporting.
+    /// Returns `None` if the live cells are spread over too large an area to copy.
+    pub fn to_grid(&self) -> Option<Grid> {
+        let Some((min_x, min_y, max_x, max_y)) = self.live_bounds() else {
+            return Some(Grid::new(1, 1));
+        };
+        let (width, height) = ((max_x - min_x + 1) as usize, (max_y - min_y + 1) as usize);
+        if width.saturating_mul(height) > MAX_GRID_CELLS {
+            return None;
+        }
+        let mut grid = Grid::new(width, height);
+        grid.set_topology(Topology::Bounded);
+        self.for_each_live_block(min_x, min_y, width, height, 0, |x, y| grid.set_alive(x, y, true));
+        Some(grid)
+    }
+}
+
+impl Board for Universe {
+    fn place_cell(&mut self, x: isize, y: isize) -> bool {
+        self.set_alive(x as i64, y as i64, true);
+        true
+    }
+}